@@ -1,14 +1,38 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
 use colored::*;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
+use crate::config::{self, Config};
+use crate::db::Migration;
 use crate::services::NoteService;
-use crate::models::Priority;
+use crate::models::{
+    ArchiveFilter, Note, NoteFilter, Notebook, Priority, Revision, SearchMode, SearchOrder, SearchResults, MATCH_END,
+    MATCH_START,
+};
+use crate::utils::diff::{unified_diff, DiffLine, Hunk};
+use crate::export::{self, JsonExport};
+use crate::import;
+use crate::utils::{parse_date, parse_duration, start_of_today};
 
 #[derive(Parser)]
 #[command(name = "ezn")]
 #[command(about = "⚡ Zero-friction note taking for developers", long_about = None)]
 #[command(version)]
 pub struct Cli {
+    /// Database file to use (overrides $EZNOTE_DB)
+    #[arg(long, global = true, value_name = "PATH")]
+    db: Option<PathBuf>,
+    
+    /// Use a named database profile from the config file
+    #[arg(long, global = true, conflicts_with = "db")]
+    profile: Option<String>,
+    
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    output: OutputFormat,
+    
     #[command(subcommand)]
     command: Commands,
 }
@@ -24,30 +48,74 @@ enum Commands {
         #[arg(short, long)]
         tag: Vec<String>,
         
-        /// Set priority (low, medium, high, urgent)
-        #[arg(short, long, default_value = "medium")]
-        priority: String,
+        /// Set priority (low, medium, high, urgent) [default: medium, or `priority` from config]
+        #[arg(short, long)]
+        priority: Option<String>,
+        
+        /// Add to this notebook instead of the current one
+        #[arg(short, long)]
+        notebook: Option<String>,
     },
     
     /// List notes
     List {
         /// Show only today's notes
-        #[arg(long)]
+        #[arg(long, conflicts_with = "since")]
         today: bool,
         
-        /// Filter by tag
+        #[command(flatten)]
+        filter: FilterArgs,
+        
+        /// Limit number of results [default: 20, or `limit` from config]
         #[arg(short, long)]
-        tag: Option<String>,
+        limit: Option<usize>,
+        
+        /// Show only archived notes
+        #[arg(long, conflicts_with_all = ["include_archived", "all"])]
+        archived: bool,
+        
+        /// Show both active and archived notes
+        #[arg(long)]
+        include_archived: bool,
         
-        /// Limit number of results
-        #[arg(short, long, default_value = "20")]
-        limit: usize,
+        #[command(flatten)]
+        scope: NotebookScope,
     },
     
     /// Search notes
     Search {
-        /// Search query
+        /// Words, "phrases" and filters like tag:work, priority:>=high, created:<7d,
+        /// combined with AND, OR, NOT (or -) and parentheses
+        #[arg(allow_hyphen_values = true)]
         query: String,
+        
+        /// Include archived notes in the results
+        #[arg(long)]
+        include_archived: bool,
+        
+        /// Order results
+        #[arg(long, value_enum, default_value = "newest")]
+        sort: SearchOrder,
+        
+        /// Search for the words as typed, without fields or operators
+        #[arg(long, conflicts_with = "raw_fts")]
+        literal: bool,
+        
+        /// Pass the query straight to SQLite's FTS5 MATCH
+        #[arg(long)]
+        raw_fts: bool,
+        
+        /// Match words anywhere, even inside other words (on by default for
+        /// queries that look like code, such as getUserById or src/auth)
+        #[arg(long, conflicts_with = "raw_fts")]
+        substring: bool,
+        
+        /// Also match words spelled a letter or two differently
+        #[arg(long, conflicts_with = "raw_fts")]
+        fuzzy: bool,
+        
+        #[command(flatten)]
+        scope: NotebookScope,
     },
     
     /// Show note details
@@ -56,7 +124,84 @@ enum Commands {
         id: i64,
     },
     
-    /// Delete a note
+    /// Edit a note in $EDITOR, or inline with flags
+    Edit {
+        /// Note ID
+        id: i64,
+        
+        /// Replace the content without opening an editor
+        #[arg(short, long)]
+        content: Option<String>,
+        
+        /// Change priority (low, medium, high, urgent)
+        #[arg(short, long)]
+        priority: Option<String>,
+        
+        /// Add a tag (can specify multiple times)
+        #[arg(long)]
+        add_tag: Vec<String>,
+        
+        /// Remove a tag (can specify multiple times)
+        #[arg(long)]
+        remove_tag: Vec<String>,
+    },
+    
+    /// Show the revision history of a note
+    History {
+        /// Note ID
+        id: i64,
+    },
+    
+    /// Compare two revisions of a note (defaults to the latest change)
+    Diff {
+        /// Note ID
+        id: i64,
+        
+        /// Older revision (defaults to the one before the latest)
+        rev_a: Option<i64>,
+        
+        /// Newer revision (defaults to the latest)
+        rev_b: Option<i64>,
+    },
+    
+    /// Roll a note back to an earlier revision
+    Revert {
+        /// Note ID
+        id: i64,
+        
+        /// Revision to restore
+        rev: i64,
+    },
+    
+    /// Archive notes so they leave the daily view
+    Archive {
+        /// Note IDs
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    
+    /// Move archived notes back into the daily view
+    Unarchive {
+        /// Note IDs
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+    
+    /// Undo the last N changes
+    Undo {
+        /// Number of changes to undo
+        #[arg(default_value = "1")]
+        count: usize,
+    },
+    
+    /// Redo changes reverted by undo
+    Redo {
+        /// Number of changes to redo
+        #[arg(default_value = "1")]
+        count: usize,
+    },
+    
+    /// Move a note to the trash
     Delete {
         /// Note ID
         id: i64,
@@ -66,19 +211,543 @@ enum Commands {
         force: bool,
     },
     
+    /// Restore a note from the trash
+    Restore {
+        /// Note ID
+        id: i64,
+    },
+    
+    /// Manage deleted notes
+    Trash {
+        #[command(subcommand)]
+        action: TrashCommands,
+    },
+    
     /// Show today's notes
-    Today,
+    Today {
+        #[command(flatten)]
+        scope: NotebookScope,
+    },
     
     /// Show statistics
-    Stats,
+    Stats {
+        #[command(flatten)]
+        scope: NotebookScope,
+    },
+    
+    /// Manage notebooks
+    Notebook {
+        #[command(subcommand)]
+        action: NotebookCommands,
+    },
+    
+    /// Export notes to Markdown, JSON, CSV, todo.txt, Org or a static HTML site
+    #[command(group(ArgGroup::new("export_format").required(true)))]
+    Export {
+        /// Output format
+        #[arg(value_enum, group = "export_format")]
+        format: Option<ExportFormat>,
+        
+        /// File to write, or directory for markdown and html (defaults to stdout)
+        #[arg(value_name = "PATH", conflicts_with = "out")]
+        path: Option<PathBuf>,
+        
+        /// Output format, as an option
+        #[arg(id = "format_option", short = 'f', long = "format", value_enum, value_name = "FORMAT", group = "export_format")]
+        format_option: Option<ExportFormat>,
+        
+        /// Same as PATH
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+        
+        /// Only export notes from this notebook (defaults to all notebooks)
+        #[arg(short, long)]
+        notebook: Option<String>,
+        
+        /// Leave out archived notes
+        #[arg(long)]
+        no_archived: bool,
+        
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    
+    /// Import notes from an `ezn export json` file or another tool
+    #[command(args_conflicts_with_subcommands = true)]
+    Import {
+        #[command(subcommand)]
+        source: Option<ImportSource>,
+        
+        /// JSON file to read
+        #[arg(required = true)]
+        file: Option<PathBuf>,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+    
+    /// Show or change settings in the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommands,
+    },
+    
+    /// Database maintenance
+    Db {
+        #[command(subcommand)]
+        action: DbCommands,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colored, human-readable text
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl OutputFormat {
+    fn is_structured(self) -> bool {
+        self != OutputFormat::Text
+    }
+    
+    /// Print one value, e.g. a note or the statistics.
+    fn print<T: Serialize>(self, value: &T) -> anyhow::Result<()> {
+        match self {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+            _ => println!("{}", serde_json::to_string(value)?),
+        }
+        Ok(())
+    }
+    
+    /// Print a list as a JSON array, or as one object per line for JSON Lines.
+    fn print_all<T: Serialize>(self, values: &[T]) -> anyhow::Result<()> {
+        match self {
+            OutputFormat::Jsonl => {
+                for value in values {
+                    println!("{}", serde_json::to_string(value)?);
+                }
+            }
+            _ => println!("{}", serde_json::to_string_pretty(values)?),
+        }
+        Ok(())
+    }
+    
+    /// For a command that ended up changing nothing, such as a cancelled delete.
+    fn print_unchanged(self) -> anyhow::Result<()> {
+        self.print(&serde_json::json!({ "changed": false }))
+    }
+}
+
+/// A search result as printed with `--output json`: the note's fields, plus
+/// the snippet (matches wrapped in `MATCH_START`/`MATCH_END`) and, when the
+/// query was searched for as literal words instead, why.
+#[derive(Serialize)]
+struct SearchRow<'a> {
+    #[serde(flatten)]
+    note: &'a Note,
+    snippet: Option<&'a str>,
+    fallback: Option<&'a str>,
+}
+
+/// `ezn diff` with `--output json`. `old` is missing when the note has only one revision.
+#[derive(Serialize)]
+struct NoteDiff<'a> {
+    id: i64,
+    old: Option<&'a Revision>,
+    new: &'a Revision,
+    hunks: Vec<Hunk<'a>>,
+}
+
+#[derive(Clone, Copy, ValueEnum, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    /// One file per note with YAML front matter
+    Markdown,
+    /// A single JSON document
+    Json,
+    /// One row per note
+    Csv,
+    /// One todo.txt task per note
+    Todotxt,
+    /// A static website with an index, tag pages and search
+    Html,
+    /// One Org heading per note
+    Org,
+}
+
+#[derive(Subcommand)]
+enum ImportSource {
+    /// Import a directory of Markdown files, such as an Obsidian vault
+    Obsidian {
+        /// Vault directory
+        dir: PathBuf,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+    
+    /// Import an Evernote .enex export
+    Enex {
+        /// ENEX file
+        file: PathBuf,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+    
+    /// Import Google Keep notes from a Takeout export
+    Keep {
+        /// Takeout directory (or its Keep folder)
+        dir: PathBuf,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+    
+    /// Import tasks from a todo.txt file
+    Todotxt {
+        /// todo.txt file
+        file: PathBuf,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+    
+    /// Import headings from an Org file
+    Org {
+        /// Org file
+        file: PathBuf,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+    
+    /// Import tasks from `task export`
+    Taskwarrior {
+        /// JSON file written by `task export`
+        file: PathBuf,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+    
+    /// Import journal entries from `jrnl --export json`
+    Jrnl {
+        /// JSON file written by `jrnl --export json`
+        file: PathBuf,
+        
+        #[command(flatten)]
+        options: ImportArgs,
+    },
+}
+
+/// Options shared by every import source.
+#[derive(Args)]
+struct ImportArgs {
+    /// Put every imported note in this notebook instead of the one it came from
+    #[arg(short, long)]
+    notebook: Option<String>,
+}
+
+/// Filters shared by `list` and `export`.
+#[derive(Args)]
+struct FilterArgs {
+    /// Filter by tag
+    #[arg(short, long)]
+    tag: Option<String>,
+    
+    /// Filter by priority (low, medium, high, urgent)
+    #[arg(short, long)]
+    priority: Option<String>,
+    
+    /// Only notes created on or after this date (2024-03-01) or within this age (7d)
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+    
+    /// Only notes created on or before this date, or before this age
+    #[arg(long, value_name = "DATE")]
+    until: Option<String>,
+}
+
+impl FilterArgs {
+    fn apply(&self, filter: &mut NoteFilter) -> anyhow::Result<()> {
+        filter.tag = self.tag.clone();
+        filter.priority = self.priority.as_deref().map(Priority::from_str).transpose()?;
+        filter.since = self.since.as_deref().map(|s| parse_date(s, false)).transpose()?;
+        filter.until = self.until.as_deref().map(|s| parse_date(s, true)).transpose()?;
+        Ok(())
+    }
+}
+
+/// Which notebook a listing covers. Defaults to the current notebook.
+#[derive(Args)]
+struct NotebookScope {
+    /// Only include notes from this notebook
+    #[arg(short, long, conflicts_with = "all")]
+    notebook: Option<String>,
+    
+    /// Include notes from every notebook, and for `list` and `search` archived notes too
+    #[arg(long)]
+    all: bool,
+}
+
+impl NotebookScope {
+    /// The notebook to filter on, or `None` for all notebooks.
+    fn resolve(&self, note_service: &NoteService) -> anyhow::Result<Option<Notebook>> {
+        if self.all {
+            return Ok(None);
+        }
+        
+        match &self.notebook {
+            Some(name) => note_service.get_notebook(name).map(Some),
+            None => note_service.current_notebook().map(Some),
+        }
+    }
+    
+    fn notebook_id(&self, note_service: &NoteService) -> anyhow::Result<Option<i64>> {
+        Ok(self.resolve(note_service)?.map(|notebook| notebook.id))
+    }
+}
+
+#[derive(Subcommand)]
+enum NotebookCommands {
+    /// Create a notebook
+    Create {
+        /// Notebook name
+        name: String,
+    },
+    
+    /// List notebooks
+    List,
+    
+    /// Rename a notebook
+    Rename {
+        /// Current name
+        name: String,
+        
+        /// New name
+        new_name: String,
+    },
+    
+    /// Delete a notebook
+    Delete {
+        /// Notebook name
+        name: String,
+        
+        /// Move the notebook's notes here instead of refusing to delete it
+        #[arg(long, value_name = "NOTEBOOK")]
+        move_to: Option<String>,
+    },
+    
+    /// Switch the current notebook
+    Use {
+        /// Notebook name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective value of a setting
+    Get {
+        /// Setting name (priority, tags, limit, date_format, theme, editor, db)
+        key: String,
+    },
+    
+    /// Change a setting
+    Set {
+        /// Setting name (priority, tags, limit, date_format, theme, editor, db)
+        key: String,
+        
+        /// New value (comma-separated for tags)
+        value: String,
+        
+        /// Write to the project's .eznote.toml instead of the user config
+        #[arg(long)]
+        local: bool,
+    },
+    
+    /// Print every effective setting
+    List,
+    
+    /// Print the config file locations
+    Path,
+}
+
+#[derive(Subcommand)]
+enum DbCommands {
+    /// Apply pending schema migrations
+    Migrate {
+        /// Only show which migrations would run
+        #[arg(long)]
+        dry_run: bool,
+    },
+    
+    /// Print notes, tags and notebooks as a SQL script
+    Dump,
+    
+    /// Replace the database contents with a `db dump` script
+    Restore {
+        /// SQL file written by `ezn db dump`
+        file: PathBuf,
+        
+        /// Skip confirmation
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashCommands {
+    /// List notes in the trash
+    List,
+    
+    /// Permanently delete notes in the trash
+    Empty {
+        /// Only purge notes trashed longer ago than this (e.g. 30d, 2w)
+        #[arg(long)]
+        older_than: Option<String>,
+        
+        /// Skip confirmation
+        #[arg(short, long)]
+        force: bool,
+    },
 }
 
 impl Cli {
-    pub fn execute(&self, note_service: &NoteService) -> anyhow::Result<()> {
+    /// The database file selected by `--db`, `--profile` or the environment.
+    pub fn db_path(&self, config: &Config) -> anyhow::Result<PathBuf> {
+        config.resolve_db_path(self.db.as_deref(), self.profile.as_deref())
+    }
+    
+    /// Whether this command handles schema migrations itself instead of
+    /// having them applied automatically at startup.
+    pub fn manages_migrations(&self) -> bool {
+        matches!(self.command, Commands::Db { action: DbCommands::Migrate { .. } })
+    }
+    
+    pub fn output(&self) -> OutputFormat {
+        self.output
+    }
+    
+    /// After "Cancelled" on stderr, tell structured output that nothing changed.
+    fn print_cancelled(&self) -> anyhow::Result<()> {
+        if self.output.is_structured() {
+            return self.output.print_unchanged();
+        }
+        Ok(())
+    }
+    
+    /// Whether this command works on notes. `ezn config` must keep working
+    /// even when the configured database can't be opened.
+    pub fn needs_database(&self) -> bool {
+        !matches!(self.command, Commands::Config { .. })
+    }
+    
+    pub fn execute_config(&self) -> anyhow::Result<()> {
+        let Commands::Config { action } = &self.command else {
+            return Ok(());
+        };
+        
+        match action {
+            ConfigCommands::Get { key } => {
+                let value = Config::load()?.get(key)?;
+                if self.output.is_structured() {
+                    return self.output.print(&serde_json::json!({ "key": key, "value": value }));
+                }
+                match value {
+                    Some(value) => println!("{}", value),
+                    None => println!("{}", "(not set)".dimmed()),
+                }
+            }
+            
+            ConfigCommands::Set { key, value, local } => {
+                let path = if *local {
+                    if key == "editor" {
+                        return Err(anyhow::anyhow!("editor can only be set in the user config, a project's {} can't choose a command to run",
+                            config::LOCAL_CONFIG_FILE));
+                    }
+                    match Config::local_path() {
+                        Some(path) => path,
+                        None => std::env::current_dir()?.join(config::LOCAL_CONFIG_FILE),
+                    }
+                } else {
+                    Config::path()?
+                };
+                
+                Config::set_in_file(&path, key, value)?;
+                if self.output.is_structured() {
+                    return self.output.print(&serde_json::json!({ "key": key, "value": value, "path": path }));
+                }
+                
+                println!("{} Set {} in {}", "✓".green().bold(), key.cyan(), path.display().to_string().dimmed());
+            }
+            
+            ConfigCommands::List => {
+                let config = Config::load()?;
+                if self.output.is_structured() {
+                    let mut settings = serde_json::Map::new();
+                    for key in config::KEYS {
+                        settings.insert(key.to_string(), config.get(key)?.into());
+                    }
+                    settings.insert("profiles".to_string(), serde_json::to_value(&config.profiles)?);
+                    return self.output.print(&settings);
+                }
+                
+                for key in config::KEYS {
+                    match config.get(key)? {
+                        Some(value) => println!("{} = {}", key.cyan(), value),
+                        None => println!("{} = {}", key.cyan(), "(not set)".dimmed()),
+                    }
+                }
+                for (name, profile) in &config.profiles {
+                    println!("{} = {}", format!("profiles.{}.db", name).cyan(), profile.db.display());
+                }
+            }
+            
+            ConfigCommands::Path => {
+                if self.output.is_structured() {
+                    return self.output.print(&serde_json::json!({
+                        "user": Config::path()?,
+                        "project": Config::local_path(),
+                    }));
+                }
+                
+                println!("User:     {}", Config::path()?.display());
+                match Config::local_path() {
+                    Some(path) => println!("Project:  {}", path.display()),
+                    None => println!("Project:  {}", format!("(no {} found)", config::LOCAL_CONFIG_FILE).dimmed()),
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    pub fn execute(&self, note_service: &NoteService, config: &Config) -> anyhow::Result<()> {
         match &self.command {
-            Commands::Add { content, tag, priority } => {
-                let priority = Priority::from_str(priority)?;
-                let note = note_service.add_note(content, tag.clone(), priority)?;
+            Commands::Add { content, tag, priority, notebook } => {
+                let priority = match priority {
+                    Some(priority) => Priority::from_str(priority)?,
+                    None => config.priority.clone().unwrap_or(Priority::Medium),
+                };
+                let notebook_id = match notebook {
+                    Some(name) => Some(note_service.get_notebook(name)?.id),
+                    None => None,
+                };
+                
+                // Configured default tags come first, without duplicating ones given on the command line
+                let mut tags = config.tags.clone();
+                tags.extend(tag.iter().filter(|t| !config.tags.contains(t)).cloned());
+                
+                let note = note_service.add_note(content, tags, priority, notebook_id)?;
+                if self.output.is_structured() {
+                    return self.output.print(&note);
+                }
                 
                 println!("{} Note added with ID: {}", 
                     "✓".green().bold(), 
@@ -86,35 +755,109 @@ impl Cli {
                 );
             }
             
-            Commands::List { today, tag, limit } => {
-                let notes = if *today {
-                    note_service.list_today(*limit)?
-                } else if let Some(tag_filter) = tag {
-                    note_service.list_by_tag(tag_filter, *limit)?
+            Commands::List { today, filter, limit, archived, include_archived, scope } => {
+                let archived = if *include_archived || scope.all {
+                    ArchiveFilter::All
+                } else if *archived {
+                    ArchiveFilter::Archived
                 } else {
-                    note_service.list_recent(*limit)?
+                    ArchiveFilter::Active
                 };
                 
+                let mut note_filter = NoteFilter {
+                    archived,
+                    notebook_id: scope.notebook_id(note_service)?,
+                    limit: Some(limit.or(config.limit).unwrap_or(20)),
+                    ..NoteFilter::default()
+                };
+                filter.apply(&mut note_filter)?;
+                if *today {
+                    note_filter.since = Some(start_of_today());
+                }
+                
+                let notes = note_service.list_notes(&note_filter)?;
+                
+                if self.output.is_structured() {
+                    return self.output.print_all(&notes);
+                }
+                
                 if notes.is_empty() {
                     println!("{}", "No notes found".dimmed());
                 } else {
                     for note in notes {
-                        print_note(&note);
+                        print_note(&note, None, config);
                         println!();
                     }
                 }
             }
             
-            Commands::Search { query } => {
-                let notes = note_service.search(query)?;
+            Commands::Search { query: text, include_archived, sort, literal, raw_fts, substring, fuzzy, scope } => {
+                let mode = if *literal {
+                    SearchMode::Literal
+                } else if *raw_fts {
+                    SearchMode::RawFts
+                } else {
+                    SearchMode::Query
+                };
+                let (parsed, parse_fallback) = NoteService::parse_query(text, mode)?;
+                let mut query = parsed.clone();
+                if *fuzzy {
+                    query = note_service.fuzzy_query(query)?;
+                }
+                // Word search splits `getUserById` and `foo-bar` apart, so code needs substrings
+                if *substring || query.has_code_terms() {
+                    query = query.into_substring();
+                }
+                // Fields named in the query take over from the defaults they'd contradict
+                let archived = if *include_archived || scope.all || query.mentions("archived") {
+                    ArchiveFilter::All
+                } else {
+                    ArchiveFilter::Active
+                };
+                let notebook_id = if query.mentions("notebook") && scope.notebook.is_none() {
+                    None
+                } else {
+                    scope.notebook_id(note_service)?
+                };
                 
-                if notes.is_empty() {
-                    println!("{}", format!("No notes found matching '{}'", query).dimmed());
+                let SearchResults { results, fallback } = note_service.search(&query, &NoteFilter {
+                    archived,
+                    notebook_id,
+                    ..NoteFilter::default()
+                }, *sort)?;
+                // Searches like `C++` or `"unterminated` should still find something
+                let fallback = parse_fallback.or(fallback);
+                if let Some(reason) = &fallback {
+                    eprintln!("{} {}", "!".yellow().bold(), reason);
+                    eprintln!("  {}", "Searching for the words as typed instead".dimmed());
+                }
+                
+                if self.output.is_structured() {
+                    let rows: Vec<_> = results.iter()
+                        .map(|result| SearchRow {
+                            note: &result.note,
+                            snippet: result.snippet.as_deref(),
+                            fallback: fallback.as_deref(),
+                        })
+                        .collect();
+                    return self.output.print_all(&rows);
+                }
+                
+                if results.is_empty() {
+                    println!("{}", format!("No notes found matching '{}'", text).dimmed());
+                    
+                    let suggestions = note_service.spelling_suggestions(&parsed)?;
+                    if !suggestions.is_empty() {
+                        println!("Did you mean: {}?", correct_spelling(text, &suggestions).bold());
+                        if !*fuzzy {
+                            println!("{}", "Search with --fuzzy to also match close spellings".dimmed());
+                        }
+                    }
                 } else {
-                    println!("Found {} notes:", notes.len());
+                    println!("Found {} notes:", results.len());
                     println!();
-                    for note in notes {
-                        print_note(&note);
+                    for result in results {
+                        print_note(&result.note, result.snippet.as_deref(), config);
                         println!();
                     }
                 }
@@ -122,31 +865,244 @@ impl Cli {
             
             Commands::Show { id } => {
                 let note = note_service.get_note(*id)?;
-                print_note_detailed(&note);
+                if self.output.is_structured() {
+                    return self.output.print(&note);
+                }
+                print_note_detailed(&note, config);
             }
             
-            Commands::Delete { id, force } => {
-                // Check if note exists first
-                if let Err(_) = note_service.get_note(*id) {
-                    return Err(anyhow::anyhow!("Note {} not found", id));
+            Commands::Edit { id, content, priority, add_tag, remove_tag } => {
+                let note = note_service.get_note(*id)?;
+                let priority = priority.as_deref().map(Priority::from_str).transpose()?;
+                
+                let interactive = content.is_none()
+                    && priority.is_none()
+                    && add_tag.is_empty()
+                    && remove_tag.is_empty();
+                
+                let content = if interactive {
+                    Some(edit_in_editor(&note.content, config)?)
+                } else {
+                    content.clone()
+                };
+                
+                if let Some(text) = &content {
+                    if text.trim().is_empty() {
+                        return Err(anyhow::anyhow!("Note content cannot be empty"));
+                    }
+                }
+                
+                let updated = note_service.update_note(*id, content.as_deref(), priority, add_tag, remove_tag)?;
+                match updated {
+                    Some(note) if self.output.is_structured() => self.output.print(&note)?,
+                    None if self.output.is_structured() => self.output.print_unchanged()?,
+                    Some(_) => println!("{} Note {} updated", "✓".green().bold(), id.to_string().cyan().bold()),
+                    None => println!("{}", "No changes".dimmed()),
+                }
+            }
+            
+            Commands::History { id } => {
+                let revisions = note_service.list_revisions(*id)?;
+                
+                if self.output.is_structured() {
+                    return self.output.print_all(&revisions);
                 }
                 
-                if !force {
-                    println!("Delete note {}? (y/N)", id.to_string().cyan());
-                    let mut input = String::new();
-                    std::io::stdin().read_line(&mut input)?;
-                    if !input.trim().eq_ignore_ascii_case("y") {
-                        println!("Cancelled");
+                println!("{}", format!("📜 History of note #{} ({} revisions)", id, revisions.len()).bold());
+                println!();
+                for rev in revisions.iter().rev() {
+                    let first_line = rev.content.lines().next().unwrap_or("");
+                    println!("{} {} {}",
+                        format!("r{}", rev.revision).bright_blue().bold(),
+                        rev.created_at.format("%Y-%m-%d %H:%M:%S").to_string().dimmed(),
+                        format_priority(&rev.priority),
+                    );
+                    println!("  {}", first_line);
+                    if !rev.tags.is_empty() {
+                        println!("  {}", format_tags(&rev.tags, " "));
+                    }
+                }
+            }
+            
+            Commands::Diff { id, rev_a, rev_b } => {
+                let revisions = note_service.list_revisions(*id)?;
+                let latest = revisions.last()
+                    .ok_or_else(|| anyhow::anyhow!("Note {} has no recorded revisions", id))?
+                    .revision;
+                
+                let (a, b) = match (rev_a, rev_b) {
+                    (Some(a), Some(b)) => (*a, *b),
+                    (Some(a), None) => (*a, latest),
+                    _ if latest > 1 => (latest - 1, latest),
+                    _ => {
+                        if self.output.is_structured() {
+                            let new = revisions.last().unwrap();
+                            return self.output.print(&NoteDiff { id: *id, old: None, new, hunks: Vec::new() });
+                        }
+                        println!("{}", format!("Note {} has only one revision", id).dimmed());
                         return Ok(());
                     }
+                };
+                
+                let old = note_service.get_revision(*id, a)?;
+                let new = note_service.get_revision(*id, b)?;
+                let hunks = unified_diff(&old.content, &new.content, 3);
+                
+                if self.output.is_structured() {
+                    return self.output.print(&NoteDiff { id: *id, old: Some(&old), new: &new, hunks });
+                }
+                
+                println!("{}", format!("--- note {} r{} ({})", id, a, old.created_at.format("%Y-%m-%d %H:%M:%S")).red().bold());
+                println!("{}", format!("+++ note {} r{} ({})", id, b, new.created_at.format("%Y-%m-%d %H:%M:%S")).green().bold());
+                
+                if old.priority != new.priority {
+                    println!("Priority: {} → {}", format_priority(&old.priority), format_priority(&new.priority));
+                }
+                if old.tags != new.tags {
+                    println!("Tags:     {} → {}", format_tags(&old.tags, " "), format_tags(&new.tags, " "));
+                }
+                
+                for hunk in hunks {
+                    println!("{}", hunk.header().cyan());
+                    for line in hunk.lines {
+                        match line {
+                            DiffLine::Context(l) => println!(" {}", l),
+                            DiffLine::Removed(l) => println!("{}", format!("-{}", l).red()),
+                            DiffLine::Added(l) => println!("{}", format!("+{}", l).green()),
+                        }
+                    }
+                }
+            }
+            
+            Commands::Revert { id, rev } => {
+                let note = note_service.revert_note(*id, *rev)?;
+                if self.output.is_structured() {
+                    return self.output.print(&note);
+                }
+                println!("{} Note {} reverted to r{}", "✓".green().bold(), id.to_string().cyan().bold(), rev);
+            }
+            
+            Commands::Archive { ids } => {
+                let notes = note_service.archive_notes(ids)?;
+                if self.output.is_structured() {
+                    return self.output.print_all(&notes);
+                }
+                for id in ids {
+                    println!("{} Note {} archived", "✓".green().bold(), id.to_string().cyan());
+                }
+            }
+            
+            Commands::Unarchive { ids } => {
+                let notes = note_service.unarchive_notes(ids)?;
+                if self.output.is_structured() {
+                    return self.output.print_all(&notes);
+                }
+                for id in ids {
+                    println!("{} Note {} unarchived", "✓".green().bold(), id.to_string().cyan());
+                }
+            }
+            
+            Commands::Undo { count } => {
+                let operations = note_service.undo(*count)?;
+                
+                if self.output.is_structured() {
+                    return self.output.print_all(&operations);
+                }
+                
+                if operations.is_empty() {
+                    println!("{}", "Nothing to undo".dimmed());
+                }
+                for op in operations {
+                    println!("{} Undid {}", "✓".green().bold(), describe_operation(&op));
+                }
+            }
+            
+            Commands::Redo { count } => {
+                let operations = note_service.redo(*count)?;
+                
+                if self.output.is_structured() {
+                    return self.output.print_all(&operations);
+                }
+                
+                if operations.is_empty() {
+                    println!("{}", "Nothing to redo".dimmed());
+                }
+                for op in operations {
+                    println!("{} Redid {}", "✓".green().bold(), describe_operation(&op));
+                }
+            }
+            
+            Commands::Delete { id, force } => {
+                // Check if note exists first
+                note_service.get_note(*id)?;
+                
+                if !force && !confirm(&format!("Move note {} to the trash?", id.to_string().cyan()))? {
+                    eprintln!("Cancelled");
+                    return self.print_cancelled();
                 }
                 
-                note_service.delete_note(*id)?;
-                println!("{} Note {} deleted", "✓".green().bold(), id);
+                let note = note_service.delete_note(*id)?;
+                if self.output.is_structured() {
+                    return self.output.print(&note);
+                }
+                println!("{} Note {} moved to trash (undo with: ezn restore {})", "✓".green().bold(), id, id);
             }
             
-            Commands::Today => {
-                let notes = note_service.list_today(100)?;
+            Commands::Restore { id } => {
+                let note = note_service.restore_note(*id)?;
+                if self.output.is_structured() {
+                    return self.output.print(&note);
+                }
+                println!("{} Note {} restored", "✓".green().bold(), id.to_string().cyan().bold());
+            }
+            
+            Commands::Trash { action } => match action {
+                TrashCommands::List => {
+                    let notes = note_service.list_trash()?;
+                    
+                    if self.output.is_structured() {
+                        return self.output.print_all(&notes);
+                    }
+                    
+                    if notes.is_empty() {
+                        println!("{}", "Trash is empty".dimmed());
+                    } else {
+                        println!("{}", format!("🗑  Trash ({})", notes.len()).bold());
+                        println!();
+                        for note in notes {
+                            print_note(&note, None, config);
+                            println!();
+                        }
+                    }
+                }
+                
+                TrashCommands::Empty { older_than, force } => {
+                    let older_than = older_than.as_deref().map(parse_duration).transpose()?;
+                    
+                    if !force && !confirm("Permanently delete notes in the trash? This cannot be undone.")? {
+                        eprintln!("Cancelled");
+                        return self.print_cancelled();
+                    }
+                    
+                    let purged = note_service.empty_trash(older_than)?;
+                    if self.output.is_structured() {
+                        return self.output.print(&serde_json::json!({ "purged": purged }));
+                    }
+                    println!("{} Purged {} notes", "✓".green().bold(), purged);
+                }
+            },
+            
+            Commands::Today { scope } => {
+                let notes = note_service.list_notes(&NoteFilter {
+                    notebook_id: scope.notebook_id(note_service)?,
+                    since: Some(start_of_today()),
+                    limit: Some(100),
+                    ..NoteFilter::default()
+                })?;
+                
+                if self.output.is_structured() {
+                    return self.output.print_all(&notes);
+                }
                 
                 if notes.is_empty() {
                     println!("{}", "No notes today yet. Add one with: ezn add \"your note\"".dimmed());
@@ -154,21 +1110,27 @@ impl Cli {
                     println!("{}", format!("📝 Today's Notes ({})", notes.len()).bold());
                     println!();
                     for note in notes {
-                        print_note(&note);
+                        print_note(&note, None, config);
                         println!();
                     }
                 }
             }
             
-            Commands::Stats => {
-                let stats = note_service.get_stats()?;
+            Commands::Stats { scope } => {
+                let notebook = scope.resolve(note_service)?;
+                let stats = note_service.get_stats(notebook.as_ref().map(|b| b.id))?;
+                if self.output.is_structured() {
+                    return self.output.print(&stats);
+                }
                 
-                println!("{}", "📊 Statistics".bold());
+                let scope_label = notebook.map_or_else(|| "all notebooks".to_string(), |b| b.name);
+                println!("{} {}", "📊 Statistics".bold(), format!("({})", scope_label).dimmed());
                 println!();
                 println!("Total notes:     {}", stats.total.to_string().cyan());
                 println!("Today:           {}", stats.today.to_string().cyan());
                 println!("This week:       {}", stats.week.to_string().cyan());
                 println!("This month:      {}", stats.month.to_string().cyan());
+                println!("Archived:        {}", stats.archived.to_string().dimmed());
                 println!();
                 println!("By Priority:");
                 println!("  Urgent:        {}", stats.urgent.to_string().red());
@@ -176,30 +1138,312 @@ impl Cli {
                 println!("  Medium:        {}", stats.medium.to_string().normal());
                 println!("  Low:           {}", stats.low.to_string().dimmed());
             }
+            
+            Commands::Notebook { action } => match action {
+                NotebookCommands::Create { name } => {
+                    let notebook = note_service.create_notebook(name)?;
+                    if self.output.is_structured() {
+                        return self.output.print(&notebook);
+                    }
+                    println!("{} Notebook {} created", "✓".green().bold(), notebook.name.cyan().bold());
+                }
+                
+                NotebookCommands::List => {
+                    let current = note_service.current_notebook()?;
+                    let notebooks = note_service.list_notebooks()?;
+                    
+                    if self.output.is_structured() {
+                        return self.output.print_all(&notebooks);
+                    }
+                    
+                    for notebook in notebooks {
+                        let marker = if notebook.id == current.id { "*".green().bold() } else { " ".normal() };
+                        println!("{} {} {}",
+                            marker,
+                            notebook.name.cyan().bold(),
+                            format!("({} notes)", notebook.note_count).dimmed()
+                        );
+                    }
+                }
+                
+                NotebookCommands::Rename { name, new_name } => {
+                    let notebook = note_service.rename_notebook(name, new_name)?;
+                    if self.output.is_structured() {
+                        return self.output.print(&notebook);
+                    }
+                    println!("{} Notebook {} renamed to {}", "✓".green().bold(), name, notebook.name.cyan().bold());
+                }
+                
+                NotebookCommands::Delete { name, move_to } => {
+                    note_service.delete_notebook(name, move_to.as_deref())?;
+                    if self.output.is_structured() {
+                        return self.output.print(&serde_json::json!({ "deleted": name, "moved_to": move_to }));
+                    }
+                    match move_to {
+                        Some(target) => println!("{} Notebook {} deleted, notes moved to {}",
+                            "✓".green().bold(), name, target.cyan().bold()),
+                        None => println!("{} Notebook {} deleted", "✓".green().bold(), name),
+                    }
+                }
+                
+                NotebookCommands::Use { name } => {
+                    let notebook = note_service.set_current_notebook(name)?;
+                    if self.output.is_structured() {
+                        return self.output.print(&notebook);
+                    }
+                    println!("{} Now using notebook {}", "✓".green().bold(), notebook.name.cyan().bold());
+                }
+            },
+            
+            Commands::Export { format, path, format_option, out, notebook, no_archived, filter } => {
+                let format = format.or(*format_option).expect("clap requires a format");
+                let out = path.as_deref().or(out.as_deref());
+                let mut note_filter = NoteFilter {
+                    archived: if *no_archived { ArchiveFilter::Active } else { ArchiveFilter::All },
+                    notebook_id: notebook.as_deref().map(|name| note_service.get_notebook(name)).transpose()?.map(|b| b.id),
+                    ..NoteFilter::default()
+                };
+                filter.apply(&mut note_filter)?;
+                
+                let notes = note_service.list_notes(&note_filter)?;
+                let count = notes.len();
+                
+                match format {
+                    ExportFormat::Markdown => {
+                        let dir = out.ok_or_else(|| {
+                            anyhow::anyhow!("Markdown export writes one file per note; give a directory to write to")
+                        })?;
+                        export::markdown::write_notes(&notes, dir)?;
+                    }
+                    ExportFormat::Json => {
+                        let document = serde_json::to_string_pretty(&JsonExport::new(notes))?;
+                        match out {
+                            Some(path) => std::fs::write(path, document + "\n")?,
+                            None => println!("{}", document),
+                        }
+                    }
+                    ExportFormat::Csv => match out {
+                        Some(path) => export::csv::write_notes(&notes, std::fs::File::create(path)?)?,
+                        None => export::csv::write_notes(&notes, std::io::stdout().lock())?,
+                    },
+                    ExportFormat::Todotxt => match out {
+                        Some(path) => export::todotxt::write_notes(&notes, std::fs::File::create(path)?)?,
+                        None => export::todotxt::write_notes(&notes, std::io::stdout().lock())?,
+                    },
+                    ExportFormat::Org => match out {
+                        Some(path) => export::org::write_notes(&notes, std::fs::File::create(path)?)?,
+                        None => export::org::write_notes(&notes, std::io::stdout().lock())?,
+                    },
+                    ExportFormat::Html => {
+                        let dir = out.ok_or_else(|| {
+                            anyhow::anyhow!("HTML export writes a folder of pages; give a directory to write to")
+                        })?;
+                        let date_format = config.date_format.as_deref().unwrap_or("%Y-%m-%d %H:%M");
+                        export::html::write_site(&notes, dir, date_format)?;
+                    }
+                }
+                
+                // Without --out the export itself is the output
+                if let Some(path) = out {
+                    if self.output.is_structured() {
+                        return self.output.print(&serde_json::json!({ "format": format, "count": count, "path": path }));
+                    }
+                    println!("{} Exported {} notes to {}", "✓".green().bold(), count, path.display());
+                }
+            }
+            
+            Commands::Import { source, file, options } => {
+                let (batch, options, origin) = match (source, file) {
+                    (Some(ImportSource::Obsidian { dir, options }), _) => {
+                        (import::obsidian::read_vault(dir)?, options, dir)
+                    }
+                    (Some(ImportSource::Enex { file, options }), _) => {
+                        (import::enex::read_enex(&read_import_file(file)?)?, options, file)
+                    }
+                    (Some(ImportSource::Keep { dir, options }), _) => {
+                        (import::keep::read_takeout(dir)?, options, dir)
+                    }
+                    (Some(ImportSource::Todotxt { file, options }), _) => {
+                        (import::todotxt::parse_todotxt(&read_import_file(file)?), options, file)
+                    }
+                    (Some(ImportSource::Org { file, options }), _) => {
+                        (import::org::parse_org(&read_import_file(file)?), options, file)
+                    }
+                    (Some(ImportSource::Taskwarrior { file, options }), _) => {
+                        (import::taskwarrior::parse_export(&read_import_file(file)?)?, options, file)
+                    }
+                    (Some(ImportSource::Jrnl { file, options }), _) => {
+                        (import::jrnl::parse_export(&read_import_file(file)?)?, options, file)
+                    }
+                    (None, Some(file)) => {
+                        let notes = import::parse_json(&read_import_file(file)?)?;
+                        (import::Batch { notes, failures: Vec::new() }, options, file)
+                    }
+                    (None, None) => unreachable!("clap requires a file or a subcommand"),
+                };
+                
+                let notebook_id = match &options.notebook {
+                    Some(name) => Some(note_service.get_notebook(name)?.id),
+                    None => None,
+                };
+                let mut summary = note_service.import_notes(&batch.notes, notebook_id)?;
+                summary.failures = batch.failures;
+                if self.output.is_structured() {
+                    return self.output.print(&summary);
+                }
+                
+                println!("{} Imported {} notes from {}", "✓".green().bold(), summary.imported.len(), origin.display());
+                if summary.duplicates > 0 {
+                    println!("  {}", format!("Skipped {} duplicates", summary.duplicates).dimmed());
+                }
+                if summary.skipped_empty > 0 {
+                    println!("  {}", format!("Skipped {} empty notes", summary.skipped_empty).dimmed());
+                }
+                if !summary.failures.is_empty() {
+                    println!("{} Could not convert {} notes:", "!".yellow().bold(), summary.failures.len());
+                    for failure in &summary.failures {
+                        println!("  {}", failure.yellow());
+                    }
+                }
+            }
+            
+            Commands::Config { .. } => self.execute_config()?,
+            
+            Commands::Db { action } => match action {
+                DbCommands::Migrate { dry_run } => {
+                    let db = note_service.database();
+                    let version = db.schema_version()?;
+                    let pending = db.pending_migrations()?;
+                    let applied = if *dry_run { Vec::new() } else { db.migrate()? };
+                    
+                    if self.output.is_structured() {
+                        let describe = |migrations: &[&Migration]| -> Vec<serde_json::Value> {
+                            migrations.iter()
+                                .map(|m| serde_json::json!({ "version": m.version, "description": m.description }))
+                                .collect()
+                        };
+                        return self.output.print(&serde_json::json!({
+                            "database": db.path(),
+                            "schema_version": db.schema_version()?,
+                            "pending": if *dry_run { describe(&pending) } else { Vec::new() },
+                            "applied": describe(&applied),
+                        }));
+                    }
+                    
+                    println!("Database:        {}", db.path().display().to_string().dimmed());
+                    println!("Schema version:  {}", version.to_string().cyan());
+                    
+                    if pending.is_empty() {
+                        println!("{} Schema is up to date", "✓".green().bold());
+                    } else if *dry_run {
+                        println!();
+                        println!("{}", format!("Pending migrations ({}):", pending.len()).bold());
+                        for migration in pending {
+                            println!("  {} {}", format!("v{}", migration.version).cyan(), migration.description);
+                        }
+                    } else {
+                        for migration in applied {
+                            println!("{} Applied v{}: {}", "✓".green().bold(), migration.version, migration.description);
+                        }
+                    }
+                }
+                
+                DbCommands::Dump => {
+                    note_service.database().dump(std::io::stdout().lock())?;
+                }
+                
+                DbCommands::Restore { file, force } => {
+                    let db = note_service.database();
+                    let sql = read_import_file(file)?;
+                    
+                    let existing = db.note_count()?;
+                    if existing > 0 && !force && !confirm(&format!(
+                        "Replace all {} notes in {} with {}?",
+                        existing,
+                        db.path().display(),
+                        file.display()
+                    ))? {
+                        eprintln!("Cancelled");
+                        return self.print_cancelled();
+                    }
+                    
+                    let backup = db.restore(&sql)?;
+                    if self.output.is_structured() {
+                        return self.output.print(&serde_json::json!({
+                            "restored": db.note_count()?,
+                            "from": file,
+                            "backup": backup,
+                        }));
+                    }
+                    println!("{} Restored {} notes from {}", "✓".green().bold(), db.note_count()?, file.display());
+                    println!("  Previous contents saved to {}", backup.display().to_string().dimmed());
+                }
+            },
         }
         
         Ok(())
     }
 }
 
-fn print_note(note: &crate::models::Note) {
+/// Print a note's summary. With an `excerpt` (a search snippet), that is shown
+/// with its matches highlighted instead of the full content.
+fn print_note(note: &crate::models::Note, excerpt: Option<&str>, config: &Config) {
+    let date_format = config.date_format.as_deref().unwrap_or("%Y-%m-%d %H:%M");
     let id_str = format!("[{}]", note.id.unwrap()).bright_blue().bold();
     let priority_str = format_priority(&note.priority);
-    let time_str = note.created_at.format("%Y-%m-%d %H:%M").to_string().dimmed();
+    let time_str = note.created_at.format(date_format).to_string().dimmed();
     
-    println!("{} {} {}", id_str, priority_str, time_str);
-    println!("  {}", note.content);
+    if let Some(deleted_at) = note.deleted_at {
+        let deleted_str = format!("(deleted {})", deleted_at.format(date_format));
+        println!("{} {} {} {}", id_str, priority_str, time_str, deleted_str.red());
+    } else if note.is_archived {
+        println!("{} {} {} {}", id_str, priority_str, time_str, "(archived)".dimmed());
+    } else {
+        println!("{} {} {}", id_str, priority_str, time_str);
+    }
+    match excerpt {
+        Some(excerpt) => println!("  {}", highlight_matches(excerpt)),
+        None => println!("  {}", note.content),
+    }
     
     if !note.tags.is_empty() {
-        let tags_str = note.tags.iter()
-            .map(|t| format!("#{}", t).cyan().to_string())
-            .collect::<Vec<_>>()
-            .join(" ");
-        println!("  {}", tags_str);
+        println!("  {}", format_tags(&note.tags, " "));
+    }
+}
+
+/// `text` with each whole word in `suggestions` replaced by its suggested
+/// spelling, keeping any parentheses or leading `-` around it.
+fn correct_spelling(text: &str, suggestions: &[(String, String)]) -> String {
+    text.split_whitespace()
+        .map(|token| {
+            let word = token.trim_start_matches(['(', '-']).trim_end_matches(')');
+            match suggestions.iter().find(|(typed, _)| typed == word) {
+                Some((_, suggestion)) if !word.is_empty() => token.replacen(word, suggestion, 1),
+                _ => token.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A search snippet on one line, with the text between `MATCH_START` and
+/// `MATCH_END` highlighted.
+fn highlight_matches(snippet: &str) -> String {
+    let mut out = String::new();
+    for (i, part) in snippet.split(MATCH_START).enumerate() {
+        match part.split_once(MATCH_END) {
+            Some((matched, rest)) if i > 0 => {
+                out.push_str(&matched.yellow().bold().to_string());
+                out.push_str(rest);
+            }
+            _ => out.push_str(part),
+        }
     }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
-fn print_note_detailed(note: &crate::models::Note) {
+fn print_note_detailed(note: &crate::models::Note, config: &Config) {
+    let date_format = config.date_format.as_deref().unwrap_or("%Y-%m-%d %H:%M:%S");
     println!();
     println!("{}", format!("Note #{}", note.id.unwrap()).bold());
     println!("{}", "─".repeat(50).dimmed());
@@ -208,18 +1452,92 @@ fn print_note_detailed(note: &crate::models::Note) {
     println!();
     println!("{}", "─".repeat(50).dimmed());
     println!("Priority:  {}", format_priority(&note.priority));
-    println!("Created:   {}", note.created_at.format("%Y-%m-%d %H:%M:%S"));
-    println!("Updated:   {}", note.updated_at.format("%Y-%m-%d %H:%M:%S"));
+    if let Some(notebook) = &note.notebook {
+        println!("Notebook:  {}", notebook.cyan());
+    }
+    println!("Created:   {}", note.created_at.format(date_format));
+    println!("Updated:   {}", note.updated_at.format(date_format));
+    if note.is_archived {
+        println!("Status:    {}", "archived".dimmed());
+    }
     
     if !note.tags.is_empty() {
-        println!("Tags:      {}", note.tags.iter()
-            .map(|t| format!("#{}", t).cyan().to_string())
-            .collect::<Vec<_>>()
-            .join(", "));
+        println!("Tags:      {}", format_tags(&note.tags, ", "));
     }
     println!();
 }
 
+fn read_import_file(path: &Path) -> anyhow::Result<String> {
+    std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Could not read {}: {}", path.display(), e))
+}
+
+/// Ask a yes/no question on stdin, defaulting to no. The question goes to
+/// stderr so it can't end up in `--output json` on stdout.
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    eprintln!("{} (y/N)", prompt);
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Open `initial` in the user's editor (the configured `editor`, then $VISUAL,
+/// then $EDITOR) and return the saved text.
+fn edit_in_editor(initial: &str, config: &Config) -> anyhow::Result<String> {
+    let editor = config.editor.clone()
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .unwrap_or_else(|| "vi".to_string());
+    
+    // Editors like "code --wait" carry their own arguments
+    let mut parts = editor.split_whitespace();
+    let program = parts.next()
+        .ok_or_else(|| anyhow::anyhow!("$EDITOR is empty"))?;
+    
+    // A fresh, unguessable file, so nobody else on the machine can plant a
+    // symlink at the path or read the note while it's being edited
+    let mut file = tempfile::Builder::new().prefix("ezn-edit-").suffix(".md").tempfile()?;
+    file.write_all(initial.as_bytes())?;
+    file.flush()?;
+    
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(file.path())
+        .status();
+    
+    let status = status.map_err(|e| anyhow::anyhow!("Failed to launch editor '{}': {}", editor, e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("Editor exited with {}, note left unchanged", status));
+    }
+    
+    // Editors may save by replacing the file, so read it back by path
+    let mut edited = std::fs::read_to_string(file.path())?;
+    
+    // Editors usually end the last line with a newline; don't count that as a change
+    if !initial.ends_with('\n') && edited.ends_with('\n') {
+        edited.pop();
+        if edited.ends_with('\r') {
+            edited.pop();
+        }
+    }
+    Ok(edited)
+}
+
+fn describe_operation(op: &crate::models::Operation) -> String {
+    let ids = op.note_ids.iter()
+        .map(|id| format!("#{}", id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{} {} {}", op.command.bold(), ids.cyan(), op.created_at.format("(%Y-%m-%d %H:%M)").to_string().dimmed())
+}
+
+fn format_tags(tags: &[String], separator: &str) -> String {
+    tags.iter()
+        .map(|t| format!("#{}", t).cyan().to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
 fn format_priority(priority: &Priority) -> ColoredString {
     match priority {
         Priority::Urgent => "URGENT".red().bold(),
@@ -0,0 +1,331 @@
+use chrono::format::{Item, StrftimeItems};
+use directories::BaseDirs;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::db::Database;
+use crate::models::Priority;
+
+/// Name of the per-project config file, looked up from the current directory upwards.
+pub const LOCAL_CONFIG_FILE: &str = ".eznote.toml";
+
+/// Keys accepted by `ezn config get` / `ezn config set`.
+pub const KEYS: &[&str] = &["priority", "tags", "limit", "date_format", "theme", "editor", "db"];
+
+/// User settings loaded from `~/.config/eznote/config.toml`, with a project's
+/// `.eznote.toml` layered on top. A project file can't set `editor`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Priority for `ezn add` when `--priority` isn't given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    
+    /// Tags added to every new note
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    
+    /// Number of notes `ezn list` shows when `--limit` isn't given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    
+    /// strftime-style format for note timestamps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<Theme>,
+    
+    /// Editor command for `ezn edit`, taking precedence over $VISUAL and $EDITOR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    
+    /// Database file used when neither `--db`, `--profile` nor $EZNOTE_DB is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub db: Option<PathBuf>,
+    
+    /// Named databases selectable with `--profile`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub db: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Default,
+    /// No colors
+    Mono,
+}
+
+impl Theme {
+    pub fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(Theme::Default),
+            "mono" => Ok(Theme::Mono),
+            _ => Err(anyhow::anyhow!("Invalid theme: {}. Use: default, mono", s)),
+        }
+    }
+    
+    pub fn to_string(self) -> &'static str {
+        match self {
+            Theme::Default => "default",
+            Theme::Mono => "mono",
+        }
+    }
+}
+
+impl Config {
+    /// Load the user config merged with the nearest project config, or
+    /// defaults if neither exists.
+    pub fn load() -> anyhow::Result<Self> {
+        let mut config = Self::load_file(&Self::path()?)?;
+        
+        if let Some(path) = Self::local_path() {
+            let mut local = Self::load_file(&path)?;
+            // A project file comes with whatever repo was cloned, so it doesn't get to pick a command to run
+            local.editor = None;
+            // Paths in a project config are relative to the project, not the cwd
+            if let Some(dir) = path.parent() {
+                local.resolve_paths(dir);
+            }
+            config.merge(local);
+        }
+        
+        Ok(config)
+    }
+    
+    /// Load a single config file without merging, or defaults if it doesn't exist.
+    pub fn load_file(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        
+        let text = std::fs::read_to_string(path)?;
+        let config: Config = toml::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", path.display(), e))?;
+        
+        if let Some(format) = &config.date_format {
+            validate_date_format(format)
+                .map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", path.display(), e))?;
+        }
+        
+        Ok(config)
+    }
+    
+    /// Set `key` in the file at `path`, keeping its other entries as they are.
+    /// The file isn't loaded as a `Config`, so a bad setting can be fixed this way.
+    pub fn set_in_file(path: &Path, key: &str, value: &str) -> anyhow::Result<()> {
+        let mut table = if path.exists() {
+            let text = std::fs::read_to_string(path)?;
+            toml::from_str::<toml::Table>(&text)
+                .map_err(|e| anyhow::anyhow!("Invalid config file {}: {}", path.display(), e))?
+        } else {
+            toml::Table::new()
+        };
+        
+        let mut parsed = Config::default();
+        parsed.set(key, value)?;
+        // Only `key` is set, so it's the only entry that can serialize; an empty tag list removes it
+        match toml::Table::try_from(&parsed)?.remove(key) {
+            Some(value) => table.insert(key.to_string(), value),
+            None => table.remove(key),
+        };
+        
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(&table)?)?;
+        Ok(())
+    }
+    
+    /// `$EZNOTE_CONFIG`, else `$XDG_CONFIG_HOME/eznote/config.toml`, else `~/.config/eznote/config.toml`.
+    pub fn path() -> anyhow::Result<PathBuf> {
+        if let Some(path) = env_path("EZNOTE_CONFIG") {
+            return Ok(path);
+        }
+        
+        let config_home = match env_path("XDG_CONFIG_HOME") {
+            Some(dir) => dir,
+            None => BaseDirs::new()
+                .ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?
+                .home_dir()
+                .join(".config"),
+        };
+        
+        Ok(config_home.join("eznote").join("config.toml"))
+    }
+    
+    /// The nearest `.eznote.toml` in the current directory or one of its parents.
+    pub fn local_path() -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        cwd.ancestors()
+            .map(|dir| dir.join(LOCAL_CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+    
+    /// Pick the database to open. In order of precedence: `--db`, `--profile`,
+    /// `$EZNOTE_DB`, the configured `db`, then the platform default.
+    pub fn resolve_db_path(&self, db: Option<&Path>, profile: Option<&str>) -> anyhow::Result<PathBuf> {
+        if let Some(path) = db {
+            return Ok(expand_home(path));
+        }
+        
+        if let Some(name) = profile {
+            let profile = self.profiles.get(name).ok_or_else(|| {
+                anyhow::anyhow!("Unknown profile '{}'. Define it under [profiles.{}] in {}",
+                    name, name, Self::path().map(|p| p.display().to_string()).unwrap_or_default())
+            })?;
+            return Ok(expand_home(&profile.db));
+        }
+        
+        if let Some(path) = env_path("EZNOTE_DB") {
+            return Ok(expand_home(&path));
+        }
+        
+        if let Some(path) = &self.db {
+            return Ok(expand_home(path));
+        }
+        
+        Database::get_db_path()
+    }
+    
+    /// Display the value of `key`, or `None` if it isn't set.
+    pub fn get(&self, key: &str) -> anyhow::Result<Option<String>> {
+        let value = match key {
+            "priority" => self.priority.as_ref().map(|p| p.to_string().to_string()),
+            "tags" => Some(self.tags.join(",")).filter(|tags| !tags.is_empty()),
+            "limit" => self.limit.map(|l| l.to_string()),
+            "date_format" => self.date_format.clone(),
+            "theme" => self.theme.map(|t| t.to_string().to_string()),
+            "editor" => self.editor.clone(),
+            "db" => self.db.as_ref().map(|p| p.display().to_string()),
+            _ => return Err(unknown_key(key)),
+        };
+        Ok(value)
+    }
+    
+    /// Parse and store `value` for `key`. Tags are given comma-separated.
+    pub fn set(&mut self, key: &str, value: &str) -> anyhow::Result<()> {
+        match key {
+            "priority" => self.priority = Some(Priority::from_str(value)?),
+            "tags" => {
+                self.tags = value.split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+            }
+            "limit" => {
+                self.limit = Some(value.parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid limit: {}. Use a positive number", value))?);
+            }
+            "date_format" => {
+                validate_date_format(value)?;
+                self.date_format = Some(value.to_string());
+            }
+            "theme" => self.theme = Some(Theme::from_str(value)?),
+            "editor" => self.editor = Some(value.to_string()),
+            "db" => self.db = Some(PathBuf::from(value)),
+            _ => return Err(unknown_key(key)),
+        }
+        Ok(())
+    }
+    
+    /// Overlay the settings `other` defines on top of these.
+    fn merge(&mut self, other: Config) {
+        if other.priority.is_some() {
+            self.priority = other.priority;
+        }
+        if !other.tags.is_empty() {
+            self.tags = other.tags;
+        }
+        if other.limit.is_some() {
+            self.limit = other.limit;
+        }
+        if other.date_format.is_some() {
+            self.date_format = other.date_format;
+        }
+        if other.theme.is_some() {
+            self.theme = other.theme;
+        }
+        if other.editor.is_some() {
+            self.editor = other.editor;
+        }
+        if other.db.is_some() {
+            self.db = other.db;
+        }
+        self.profiles.extend(other.profiles);
+    }
+    
+    fn resolve_paths(&mut self, dir: &Path) {
+        let resolve = |path: &Path| {
+            if path.is_relative() && !path.starts_with("~") {
+                dir.join(path)
+            } else {
+                path.to_path_buf()
+            }
+        };
+        
+        self.db = self.db.as_deref().map(resolve);
+        for profile in self.profiles.values_mut() {
+            profile.db = resolve(&profile.db);
+        }
+    }
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow::anyhow!("Unknown config key '{}'. Valid keys: {}", key, KEYS.join(", "))
+}
+
+/// chrono panics when displaying a date with a bad format, so reject those up front.
+fn validate_date_format(format: &str) -> anyhow::Result<()> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(anyhow::anyhow!("Invalid date_format '{}'", format));
+    }
+    Ok(())
+}
+
+fn env_path(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Expand a leading `~/` so config files can use home-relative paths.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), BaseDirs::new()) {
+        (Ok(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn set_in_file_repairs_a_bad_setting_and_keeps_the_rest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "date_format = \"%Q\"\nlimit = 5\n\n[profiles.work]\ndb = \"work.db\"\n").unwrap();
+        assert!(Config::load_file(&path).is_err());
+        
+        Config::set_in_file(&path, "date_format", "%Y-%m-%d").unwrap();
+        let config = Config::load_file(&path).unwrap();
+        assert_eq!(config.date_format.as_deref(), Some("%Y-%m-%d"));
+        assert_eq!(config.limit, Some(5));
+        assert_eq!(config.profiles["work"].db, PathBuf::from("work.db"));
+        
+        Config::set_in_file(&path, "tags", "a, b").unwrap();
+        assert_eq!(Config::load_file(&path).unwrap().tags, vec!["a", "b"]);
+        Config::set_in_file(&path, "tags", "").unwrap();
+        assert!(Config::load_file(&path).unwrap().tags.is_empty());
+        
+        assert!(Config::set_in_file(&path, "limit", "lots").is_err());
+        assert_eq!(Config::load_file(&path).unwrap().limit, Some(5));
+    }
+}
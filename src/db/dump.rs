@@ -0,0 +1,147 @@
+use rusqlite::types::ValueRef;
+use std::io::Write;
+use std::path::PathBuf;
+
+use super::migrations::{latest_version, MIGRATIONS};
+use super::Database;
+
+/// Tables written by `ezn db dump`, parents before children. Revisions, the
+/// undo journal and the full-text indexes are left out: the first two are
+/// local history, and the indexes are rebuilt from `notes` on restore.
+const DUMP_TABLES: &[&str] = &["notebooks", "settings", "notes", "tags", "note_tags"];
+
+/// Every table a restore replaces, children before parents.
+const RESTORE_DROPS: &[&str] = &[
+    "note_tags",
+    "note_revisions",
+    "operation_changes",
+    "operation_notebooks",
+    "operations",
+    "notes_fts",
+    "notes_trigram",
+    "notes",
+    "tags",
+    "settings",
+    "notebooks",
+];
+
+const VERSION_HEADER: &str = "-- schema_version:";
+
+impl Database {
+    /// Write the database's data as SQL `INSERT` statements under a header
+    /// recording the schema version, for `restore` to read back.
+    pub fn dump(&self, mut out: impl Write) -> anyhow::Result<()> {
+        writeln!(out, "-- ezn database dump")?;
+        writeln!(out, "{} {}", VERSION_HEADER, self.schema_version()?)?;
+        writeln!(out, "-- dumped_at: {}", chrono::Utc::now().to_rfc3339())?;
+        writeln!(out, "-- Restore with `ezn db restore <file>`.")?;
+        
+        for table in DUMP_TABLES {
+            if !self.has_table(table)? {
+                continue;
+            }
+            
+            let mut stmt = self.conn.prepare(&format!("SELECT * FROM {} ORDER BY rowid", table))?;
+            let columns = stmt.column_names().join(", ");
+            let column_count = stmt.column_count();
+            
+            writeln!(out)?;
+            let mut rows = stmt.query([])?;
+            while let Some(row) = rows.next()? {
+                let values = (0..column_count)
+                    .map(|i| Ok(sql_literal(row.get_ref(i)?)))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                writeln!(out, "INSERT INTO {} ({}) VALUES ({});", table, columns, values.join(", "))?;
+            }
+        }
+        
+        out.flush()?;
+        Ok(())
+    }
+    
+    /// Replace everything in the database with the contents of a dump. The
+    /// schema is recreated at the dump's version, the data loaded and the
+    /// full-text indexes rebuilt, then any newer migrations run. The previous
+    /// contents are first copied to a `.pre-restore.bak` file next to the
+    /// database, whose path is returned.
+    pub fn restore(&self, sql: &str) -> anyhow::Result<PathBuf> {
+        let version = dump_version(sql)?;
+        if version > latest_version() {
+            return Err(anyhow::anyhow!(
+                "Dump uses schema version {}, but this ezn only understands up to {}. Please upgrade ezn.",
+                version,
+                latest_version()
+            ));
+        }
+        
+        let backup = self.path.with_extension("pre-restore.bak");
+        if backup.exists() {
+            std::fs::remove_file(&backup)?;
+        }
+        self.conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])?;
+        
+        let tx = self.conn.unchecked_transaction()?;
+        
+        for table in RESTORE_DROPS {
+            self.conn.execute_batch(&format!("DROP TABLE IF EXISTS {};", table))?;
+        }
+        for migration in MIGRATIONS.iter().filter(|m| m.version <= version) {
+            migration.apply(&self.conn)?;
+        }
+        // Migrations seed rows (such as the default notebook) that the dump carries itself
+        for table in DUMP_TABLES {
+            if self.has_table(table)? {
+                self.conn.execute_batch(&format!("DELETE FROM {};", table))?;
+            }
+        }
+        
+        self.conn.execute_batch(sql)
+            .map_err(|e| anyhow::anyhow!("Could not restore dump: {}", e))?;
+        for index in ["notes_fts", "notes_trigram"] {
+            if self.has_table(index)? {
+                self.conn.execute(&format!("INSERT INTO {0}({0}) VALUES ('rebuild')", index), [])?;
+            }
+        }
+        self.conn.pragma_update(None, "user_version", version)?;
+        
+        tx.commit()?;
+        
+        self.migrate()?;
+        Ok(backup)
+    }
+    
+    /// Every note, including archived and trashed ones.
+    pub fn note_count(&self) -> anyhow::Result<i64> {
+        Ok(self.conn.query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))?)
+    }
+    
+    fn has_table(&self, name: &str) -> anyhow::Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+            [name],
+            |row| row.get(0),
+        )?)
+    }
+}
+
+/// The version in a dump's `-- schema_version:` header.
+fn dump_version(sql: &str) -> anyhow::Result<i64> {
+    sql.lines()
+        .take_while(|line| line.starts_with("--"))
+        .find_map(|line| line.strip_prefix(VERSION_HEADER))
+        .and_then(|version| version.trim().parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Not an ezn dump: missing the `{}` header", VERSION_HEADER))
+}
+
+fn sql_literal(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => format!("{:?}", f),
+        ValueRef::Text(text) => format!("'{}'", String::from_utf8_lossy(text).replace('\'', "''")),
+        ValueRef::Blob(bytes) => {
+            let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            format!("X'{}'", hex)
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use rusqlite::Connection;
+
+/// One step in the schema history. `version` is what `PRAGMA user_version`
+/// reads once the step has been applied.
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    up: fn(&Connection) -> anyhow::Result<()>,
+}
+
+impl Migration {
+    pub fn apply(&self, conn: &Connection) -> anyhow::Result<()> {
+        (self.up)(conn)
+    }
+}
+
+/// Every schema change, oldest first. Append new steps here and never edit a
+/// released one. Databases created before versioning report `user_version = 0`
+/// but may already contain some of these objects, so each step must tolerate
+/// finding its changes already in place.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "Initial schema: notes, tags and full-text index",
+        up: initial_schema,
+    },
+    Migration {
+        version: 2,
+        description: "Trash: track deleted_at on notes",
+        up: trash,
+    },
+    Migration {
+        version: 3,
+        description: "Revision history for notes",
+        up: revisions,
+    },
+    Migration {
+        version: 4,
+        description: "Operation journal for undo/redo",
+        up: journal,
+    },
+    Migration {
+        version: 5,
+        description: "Notebooks and per-database settings",
+        up: notebooks,
+    },
+    Migration {
+        version: 6,
+        description: "Trigram index for substring search",
+        up: trigram_index,
+    },
+    Migration {
+        version: 7,
+        description: "Journal notebooks created by an operation",
+        up: journal_notebooks,
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+fn initial_schema(conn: &Connection) -> anyhow::Result<()> {
+    // The first release maintained `notes_fts` with plain UPDATE/DELETE statements,
+    // which leaves stale tokens behind in an external-content FTS5 table
+    let repair_fts = has_legacy_fts_triggers(conn)?;
+    if repair_fts {
+        conn.execute_batch(
+            "DROP TRIGGER IF EXISTS notes_ad; DROP TRIGGER IF EXISTS notes_au;"
+        )?;
+    }
+    
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS notes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            content TEXT NOT NULL,
+            priority TEXT DEFAULT 'medium' CHECK(priority IN ('low', 'medium', 'high', 'urgent')),
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            is_archived INTEGER DEFAULT 0
+        );
+        
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL
+        );
+        
+        CREATE TABLE IF NOT EXISTS note_tags (
+            note_id INTEGER NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (note_id, tag_id),
+            FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+        
+        CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
+            content,
+            content='notes',
+            content_rowid='id'
+        );
+        
+        CREATE TRIGGER IF NOT EXISTS notes_ai AFTER INSERT ON notes BEGIN
+            INSERT INTO notes_fts(rowid, content) VALUES (new.id, new.content);
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS notes_ad AFTER DELETE ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, content) VALUES ('delete', old.id, old.content);
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS notes_au AFTER UPDATE OF content ON notes BEGIN
+            INSERT INTO notes_fts(notes_fts, rowid, content) VALUES ('delete', old.id, old.content);
+            INSERT INTO notes_fts(rowid, content) VALUES (new.id, new.content);
+        END;
+        
+        CREATE INDEX IF NOT EXISTS idx_notes_created_at ON notes(created_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_notes_priority ON notes(priority);
+        "#
+    )?;
+    
+    if repair_fts {
+        conn.execute("INSERT INTO notes_fts(notes_fts) VALUES ('rebuild')", [])?;
+    }
+    
+    Ok(())
+}
+
+fn trash(conn: &Connection) -> anyhow::Result<()> {
+    add_column_if_missing(conn, "notes", "deleted_at", "TEXT")?;
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_notes_deleted_at ON notes(deleted_at);"
+    )?;
+    Ok(())
+}
+
+fn revisions(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS note_revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            note_id INTEGER NOT NULL,
+            revision INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            priority TEXT NOT NULL,
+            tags TEXT NOT NULL DEFAULT '[]',
+            created_at TEXT NOT NULL,
+            UNIQUE (note_id, revision),
+            FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE
+        );
+        "#
+    )?;
+    Ok(())
+}
+
+fn journal(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS operations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            command TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            undone INTEGER NOT NULL DEFAULT 0
+        );
+        
+        CREATE TABLE IF NOT EXISTS operation_changes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            operation_id INTEGER NOT NULL,
+            note_id INTEGER NOT NULL,
+            before_state TEXT,
+            after_state TEXT,
+            FOREIGN KEY (operation_id) REFERENCES operations(id) ON DELETE CASCADE
+        );
+        "#
+    )?;
+    Ok(())
+}
+
+fn notebooks(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS notebooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        "#
+    )?;
+    
+    // Existing notes land in a "default" notebook, which starts out as the current one
+    conn.execute(
+        "INSERT OR IGNORE INTO notebooks (id, name, created_at) VALUES (1, 'default', ?1)",
+        [chrono::Utc::now().to_rfc3339()],
+    )?;
+    conn.execute(
+        "INSERT OR IGNORE INTO settings (key, value) VALUES ('current_notebook', '1')",
+        [],
+    )?;
+    
+    add_column_if_missing(conn, "notes", "notebook_id", "INTEGER REFERENCES notebooks(id)")?;
+    conn.execute_batch(
+        r#"
+        UPDATE notes SET notebook_id = 1 WHERE notebook_id IS NULL;
+        CREATE INDEX IF NOT EXISTS idx_notes_notebook_id ON notes(notebook_id);
+        "#
+    )?;
+    
+    Ok(())
+}
+
+/// A second full-text index over the same content, split into three-character
+/// pieces instead of words, so any substring of three or more characters can
+/// be looked up: `auth` in `OAuthProvider`, or `user/mod.rs` in a path.
+fn trigram_index(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS notes_trigram USING fts5(
+            content,
+            content='notes',
+            content_rowid='id',
+            tokenize='trigram'
+        );
+        
+        CREATE TRIGGER IF NOT EXISTS notes_trigram_ai AFTER INSERT ON notes BEGIN
+            INSERT INTO notes_trigram(rowid, content) VALUES (new.id, new.content);
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS notes_trigram_ad AFTER DELETE ON notes BEGIN
+            INSERT INTO notes_trigram(notes_trigram, rowid, content) VALUES ('delete', old.id, old.content);
+        END;
+        
+        CREATE TRIGGER IF NOT EXISTS notes_trigram_au AFTER UPDATE OF content ON notes BEGIN
+            INSERT INTO notes_trigram(notes_trigram, rowid, content) VALUES ('delete', old.id, old.content);
+            INSERT INTO notes_trigram(rowid, content) VALUES (new.id, new.content);
+        END;
+        "#
+    )?;
+    conn.execute("INSERT INTO notes_trigram(notes_trigram) VALUES ('rebuild')", [])?;
+    Ok(())
+}
+
+/// Notebooks an operation created along the way, such as those named in an
+/// import, so undo can remove them and redo can bring them back.
+fn journal_notebooks(conn: &Connection) -> anyhow::Result<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS operation_notebooks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            operation_id INTEGER NOT NULL,
+            notebook_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (operation_id) REFERENCES operations(id) ON DELETE CASCADE
+        );
+        "#
+    )?;
+    Ok(())
+}
+
+fn has_legacy_fts_triggers(conn: &Connection) -> anyhow::Result<bool> {
+    let mut stmt = conn.prepare(
+        "SELECT sql FROM sqlite_master WHERE type = 'trigger' AND name = 'notes_au'"
+    )?;
+    let mut rows = stmt.query([])?;
+    
+    match rows.next()? {
+        Some(row) => Ok(row.get::<_, String>(0)?.contains("UPDATE notes_fts")),
+        None => Ok(false),
+    }
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> anyhow::Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect::<Result<Vec<_>, _>>()?
+        .iter()
+        .any(|name| name == column);
+    
+    if !exists {
+        conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, decl))?;
+    }
+    
+    Ok(())
+}
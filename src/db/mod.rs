@@ -1,29 +1,48 @@
 use rusqlite::Connection;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use directories::ProjectDirs;
 
+mod dump;
+mod migrations;
+
+pub use migrations::Migration;
+use migrations::{latest_version, MIGRATIONS};
+
 pub struct Database {
     conn: Connection,
+    path: PathBuf,
 }
 
 impl Database {
-    pub fn new() -> anyhow::Result<Self> {
-        let db_path = Self::get_db_path()?;
-        
+    /// Open a database without migrating it. Refuses databases written by a
+    /// newer ezn, since this binary can't know what their schema means.
+    pub fn open(db_path: &Path) -> anyhow::Result<Self> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
         
-        let conn = Connection::open(&db_path)?;
+        let conn = Connection::open(db_path)?;
+        
+        // SQLite leaves foreign keys off by default; purging notes relies on the cascades
+        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+        
+        let db = Database { conn, path: db_path.to_path_buf() };
         
-        // Initialize schema
-        Self::init_schema(&conn)?;
+        let version = db.schema_version()?;
+        if version > latest_version() {
+            return Err(anyhow::anyhow!(
+                "Database {} uses schema version {}, but this ezn only understands up to {}. Please upgrade ezn.",
+                db_path.display(),
+                version,
+                latest_version()
+            ));
+        }
         
-        Ok(Database { conn })
+        Ok(db)
     }
     
-    fn get_db_path() -> anyhow::Result<PathBuf> {
+    pub fn get_db_path() -> anyhow::Result<PathBuf> {
         let proj_dirs = ProjectDirs::from("com", "amritesh", "eznote")
             .ok_or_else(|| anyhow::anyhow!("Could not determine project directory"))?;
         
@@ -31,53 +50,56 @@ impl Database {
         Ok(data_dir.join("notes.db"))
     }
     
-    fn init_schema(conn: &Connection) -> anyhow::Result<()> {
-        conn.execute_batch(
-            r#"
-            CREATE TABLE IF NOT EXISTS notes (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                content TEXT NOT NULL,
-                priority TEXT DEFAULT 'medium' CHECK(priority IN ('low', 'medium', 'high', 'urgent')),
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                is_archived INTEGER DEFAULT 0
-            );
-            
-            CREATE TABLE IF NOT EXISTS tags (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT UNIQUE NOT NULL
-            );
-            
-            CREATE TABLE IF NOT EXISTS note_tags (
-                note_id INTEGER NOT NULL,
-                tag_id INTEGER NOT NULL,
-                PRIMARY KEY (note_id, tag_id),
-                FOREIGN KEY (note_id) REFERENCES notes(id) ON DELETE CASCADE,
-                FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
-            );
-            
-            CREATE VIRTUAL TABLE IF NOT EXISTS notes_fts USING fts5(
-                content,
-                content='notes',
-                content_rowid='id'
-            );
-            
-            CREATE TRIGGER IF NOT EXISTS notes_ai AFTER INSERT ON notes BEGIN
-                INSERT INTO notes_fts(rowid, content) VALUES (new.id, new.content);
-            END;
-            
-            CREATE TRIGGER IF NOT EXISTS notes_ad AFTER DELETE ON notes BEGIN
-                DELETE FROM notes_fts WHERE rowid = old.id;
-            END;
-            
-            CREATE TRIGGER IF NOT EXISTS notes_au AFTER UPDATE ON notes BEGIN
-                UPDATE notes_fts SET content = new.content WHERE rowid = old.id;
-            END;
-            
-            CREATE INDEX IF NOT EXISTS idx_notes_created_at ON notes(created_at DESC);
-            CREATE INDEX IF NOT EXISTS idx_notes_priority ON notes(priority);
-            "#
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+    
+    pub fn schema_version(&self) -> anyhow::Result<i64> {
+        Ok(self.conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+    
+    pub fn pending_migrations(&self) -> anyhow::Result<Vec<&'static Migration>> {
+        let version = self.schema_version()?;
+        Ok(MIGRATIONS.iter().filter(|m| m.version > version).collect())
+    }
+    
+    /// Apply pending migrations in order, each in its own transaction.
+    /// Returns the migrations that were applied.
+    pub fn migrate(&self) -> anyhow::Result<Vec<&'static Migration>> {
+        let pending = self.pending_migrations()?;
+        if pending.is_empty() {
+            return Ok(pending);
+        }
+        
+        self.backup_before_migration()?;
+        
+        for migration in &pending {
+            let tx = self.conn.unchecked_transaction()?;
+            migration.apply(&self.conn).map_err(|e| {
+                anyhow::anyhow!("Migration {} ({}) failed: {}", migration.version, migration.description, e)
+            })?;
+            self.conn.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()?;
+        }
+        
+        Ok(pending)
+    }
+    
+    /// Snapshot an existing database next to itself before changing its schema.
+    fn backup_before_migration(&self) -> anyhow::Result<()> {
+        let has_notes: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'notes')",
+            [],
+            |row| row.get(0),
         )?;
+        if !has_notes {
+            return Ok(());
+        }
+        
+        let backup = self.path.with_extension(format!("v{}.bak", self.schema_version()?));
+        if !backup.exists() {
+            self.conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])?;
+        }
         
         Ok(())
     }
@@ -86,3 +108,99 @@ impl Database {
         &self.conn
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+    
+    #[test]
+    fn migrates_a_version_1_database_to_the_latest() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.db");
+        {
+            let conn = Connection::open(&path).unwrap();
+            MIGRATIONS[0].apply(&conn).unwrap();
+            conn.execute_batch(
+                r#"
+                PRAGMA user_version = 1;
+                INSERT INTO notes (content, priority, created_at, updated_at)
+                    VALUES ('Weekly meeting about OAuthProvider', 'high', '2024-01-01T00:00:00+00:00', '2024-01-01T00:00:00+00:00');
+                INSERT INTO tags (name) VALUES ('work');
+                INSERT INTO note_tags (note_id, tag_id) VALUES (1, 1);
+                "#
+            ).unwrap();
+        }
+        
+        let db = Database::open(&path).unwrap();
+        assert_eq!(db.pending_migrations().unwrap().iter().map(|m| m.version).collect::<Vec<_>>(), vec![2, 3, 4, 5, 6, 7]);
+        assert_eq!(db.migrate().unwrap().len(), 6);
+        assert_eq!(db.schema_version().unwrap(), latest_version());
+        assert!(db.migrate().unwrap().is_empty());
+        assert!(dir.path().join("notes.v1.bak").exists());
+        
+        let conn = db.connection();
+        assert_eq!(count(conn, "SELECT notebook_id FROM notes WHERE id = 1"), 1);
+        assert_eq!(count(conn, "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL"), 1);
+        assert_eq!(count(conn, "SELECT COUNT(*) FROM notes_fts WHERE notes_fts MATCH 'meeting'"), 1);
+        // Existing notes are in the new substring index too
+        assert_eq!(count(conn, "SELECT COUNT(*) FROM notes_trigram WHERE notes_trigram MATCH 'auth'"), 1);
+        
+        let note = crate::services::NoteService::new(db).get_note(1).unwrap();
+        assert_eq!(note.tags, vec!["work"]);
+        assert_eq!(note.priority, crate::models::Priority::High);
+    }
+    
+    #[test]
+    fn repairs_the_full_text_index_of_an_unversioned_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.db");
+        {
+            // The first release's schema, whose update trigger left old words in the index
+            let conn = Connection::open(&path).unwrap();
+            conn.execute_batch(
+                r#"
+                CREATE TABLE notes (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    content TEXT NOT NULL,
+                    priority TEXT DEFAULT 'medium',
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    is_archived INTEGER DEFAULT 0
+                );
+                CREATE VIRTUAL TABLE notes_fts USING fts5(content, content='notes', content_rowid='id');
+                CREATE TRIGGER notes_ai AFTER INSERT ON notes BEGIN
+                    INSERT INTO notes_fts(rowid, content) VALUES (new.id, new.content);
+                END;
+                CREATE TRIGGER notes_au AFTER UPDATE ON notes BEGIN
+                    UPDATE notes_fts SET content = new.content WHERE rowid = new.id;
+                END;
+                INSERT INTO notes (content, created_at, updated_at) VALUES ('draft', '2024-01-01', '2024-01-01');
+                UPDATE notes SET content = 'final' WHERE id = 1;
+                "#
+            ).unwrap();
+            assert_eq!(count(&conn, "SELECT COUNT(*) FROM notes_fts WHERE notes_fts MATCH 'draft'"), 1);
+        }
+        
+        let db = Database::open(&path).unwrap();
+        assert_eq!(db.migrate().unwrap().len(), MIGRATIONS.len());
+        
+        let conn = db.connection();
+        assert_eq!(count(conn, "SELECT COUNT(*) FROM notes_fts WHERE notes_fts MATCH 'final'"), 1);
+        assert_eq!(count(conn, "SELECT COUNT(*) FROM notes_fts WHERE notes_fts MATCH 'draft'"), 0);
+        assert_eq!(count(conn, "SELECT notebook_id FROM notes WHERE id = 1"), 1);
+    }
+    
+    #[test]
+    fn refuses_a_database_from_a_newer_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.db");
+        Connection::open(&path).unwrap().execute_batch("PRAGMA user_version = 99;").unwrap();
+        
+        let error = Database::open(&path).err().unwrap().to_string();
+        assert!(error.contains("uses schema version 99"), "{}", error);
+    }
+}
@@ -0,0 +1,27 @@
+use std::io::Write;
+
+use crate::models::Note;
+
+const HEADER: [&str; 8] = ["id", "created_at", "updated_at", "priority", "archived", "notebook", "tags", "content"];
+
+/// Write one row per note. Tags share a single comma-separated column.
+pub fn write_notes(notes: &[Note], out: impl Write) -> anyhow::Result<()> {
+    let mut writer = ::csv::Writer::from_writer(out);
+    writer.write_record(HEADER)?;
+    
+    for note in notes {
+        writer.write_record([
+            note.id.map(|id| id.to_string()).unwrap_or_default(),
+            note.created_at.to_rfc3339(),
+            note.updated_at.to_rfc3339(),
+            note.priority.to_string().to_string(),
+            note.is_archived.to_string(),
+            note.notebook.clone().unwrap_or_default(),
+            note.tags.join(","),
+            note.content.clone(),
+        ])?;
+    }
+    
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,298 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use crate::models::{Note, LINK_SCHEME};
+use crate::utils::slugify;
+
+use super::file_stem;
+
+const STYLE: &str = r#"body { font-family: system-ui, sans-serif; max-width: 52rem; margin: 2rem auto; padding: 0 1rem; color: #222; line-height: 1.5; }
+a { color: #0b5cad; text-decoration: none; }
+a:hover { text-decoration: underline; }
+header p, .meta, .date { color: #666; font-size: 0.9rem; }
+#search { width: 100%; padding: 0.5rem; font-size: 1rem; box-sizing: border-box; margin-bottom: 1rem; }
+ul.notes { list-style: none; padding: 0; }
+ul.notes li { padding: 0.4rem 0; border-bottom: 1px solid #eee; }
+.tags a { margin-right: 0.5rem; }
+.badge { display: inline-block; padding: 0 0.4rem; border-radius: 0.25rem; font-size: 0.75rem; text-transform: uppercase; color: #fff; }
+.badge.low { background: #2e8b57; }
+.badge.medium { background: #b8860b; }
+.badge.high { background: #c0392b; }
+.badge.urgent { background: #8e44ad; }
+.badge.archived { background: #888; }
+.content { white-space: pre-wrap; overflow-wrap: anywhere; }
+"#;
+
+/// Filters the note list on the index page against `SEARCH_INDEX`.
+const SEARCH_SCRIPT: &str = r#"const input = document.getElementById("search");
+const list = document.getElementById("notes");
+const results = document.getElementById("results");
+
+input.addEventListener("input", () => {
+  const terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+  list.hidden = terms.length > 0;
+  results.hidden = terms.length === 0;
+  results.replaceChildren();
+  if (terms.length === 0) return;
+  
+  const matches = SEARCH_INDEX.filter(note => {
+    const text = [note.title, note.content, ...note.tags].join(" ").toLowerCase();
+    return terms.every(term => text.includes(term));
+  });
+  for (const note of matches) {
+    const link = document.createElement("a");
+    link.href = note.url;
+    link.textContent = note.title;
+    const badge = document.createElement("span");
+    badge.className = "badge " + note.priority;
+    badge.textContent = note.priority;
+    const date = document.createElement("span");
+    date.className = "date";
+    date.textContent = note.created_at;
+    const item = document.createElement("li");
+    item.append(link, " ", badge, " ", date);
+    results.append(item);
+  }
+  if (matches.length === 0) {
+    const item = document.createElement("li");
+    item.textContent = "No matching notes";
+    results.append(item);
+  }
+});
+"#;
+
+/// One note in `search-index.js`.
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    url: String,
+    title: String,
+    priority: &'a str,
+    tags: &'a [String],
+    created_at: String,
+    content: &'a str,
+}
+
+/// Write a static site for `notes` under `dir`: `index.html` listing every
+/// note newest first, a page per tag under `tags/`, a page per note under
+/// `notes/`, and a search index. Everything links relatively and nothing is
+/// loaded from elsewhere, so the folder can be opened straight from a file
+/// share. The search index is JSON assigned to a variable in a script, since
+/// browsers won't `fetch` local files. Returns the files written.
+pub fn write_site(notes: &[Note], dir: &Path, date_format: &str) -> anyhow::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir.join("notes"))?;
+    std::fs::create_dir_all(dir.join("tags"))?;
+    
+    let mut notes: Vec<&Note> = notes.iter().collect();
+    notes.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+    
+    let note_pages: HashMap<i64, String> = notes.iter()
+        .filter_map(|note| Some((note.id?, page_name(note))))
+        .collect();
+    
+    let mut by_tag: BTreeMap<&str, Vec<&Note>> = BTreeMap::new();
+    for note in &notes {
+        for tag in &note.tags {
+            by_tag.entry(tag).or_default().push(note);
+        }
+    }
+    let tag_pages = tag_page_names(by_tag.keys().copied());
+    
+    let mut written = Vec::new();
+    let mut write = |path: PathBuf, contents: String| -> anyhow::Result<()> {
+        std::fs::write(&path, contents)?;
+        written.push(path);
+        Ok(())
+    };
+    
+    write(dir.join("style.css"), STYLE.to_string())?;
+    
+    for note in &notes {
+        let body = render_note(note, &note_pages, &tag_pages, date_format);
+        write(dir.join("notes").join(page_name(note)), page(&title(note), "../", &body))?;
+    }
+    
+    for (tag, tagged) in &by_tag {
+        let body = format!(
+            "<nav><a href=\"../index.html\">&larr; All notes</a></nav>\n<h1>#{}</h1>\n<p class=\"meta\">{} notes</p>\n{}",
+            escape(tag),
+            tagged.len(),
+            note_list(tagged, "../", date_format, None)
+        );
+        write(dir.join("tags").join(&tag_pages[tag]), page(&format!("#{}", tag), "../", &body))?;
+    }
+    
+    let entries: Vec<SearchEntry> = notes.iter()
+        .map(|note| SearchEntry {
+            url: format!("notes/{}", page_name(note)),
+            title: title(note),
+            priority: note.priority.to_string(),
+            tags: &note.tags,
+            created_at: note.created_at.format(date_format).to_string(),
+            content: &note.content,
+        })
+        .collect();
+    write(dir.join("search-index.js"), format!("const SEARCH_INDEX = {};\n", serde_json::to_string(&entries)?))?;
+    
+    let tag_links = by_tag.iter()
+        .map(|(tag, tagged)| format!("<a href=\"tags/{}\">#{}</a> ({})", tag_pages[tag], escape(tag), tagged.len()))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let body = format!(
+        "<header>\n<h1>Notes</h1>\n<p>{} notes, exported {}</p>\n</header>\n\
+         <input id=\"search\" type=\"search\" placeholder=\"Search notes\" autofocus>\n\
+         <p class=\"tags\">{}</p>\n{}\n<ul id=\"results\" class=\"notes\" hidden></ul>\n\
+         <script src=\"search-index.js\"></script>\n<script>\n{}</script>",
+        notes.len(),
+        chrono::Utc::now().format(date_format),
+        tag_links,
+        note_list(&notes, "", date_format, Some("notes")),
+        SEARCH_SCRIPT
+    );
+    write(dir.join("index.html"), page("Notes", "", &body))?;
+    
+    Ok(written)
+}
+
+fn page_name(note: &Note) -> String {
+    format!("{}.html", file_stem(note))
+}
+
+/// File names for each tag's page, made unique where tags slugify alike.
+fn tag_page_names<'a>(tags: impl Iterator<Item = &'a str>) -> HashMap<&'a str, String> {
+    let mut names = HashMap::new();
+    let mut used = HashMap::new();
+    for tag in tags {
+        let slug = Some(slugify(tag)).filter(|s| !s.is_empty()).unwrap_or_else(|| "tag".to_string());
+        let count = used.entry(slug.clone()).or_insert(0);
+        *count += 1;
+        let name = if *count == 1 { format!("{}.html", slug) } else { format!("{}-{}.html", slug, count) };
+        names.insert(tag, name);
+    }
+    names
+}
+
+/// The first non-empty line without any heading marker.
+fn title(note: &Note) -> String {
+    let line = note.content.lines()
+        .map(|line| line.trim().trim_start_matches('#').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or("");
+    
+    if line.is_empty() {
+        format!("Note {}", note.id.unwrap_or_default())
+    } else if line.chars().count() > 80 {
+        format!("{}…", line.chars().take(79).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+fn page(title: &str, root: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"{}style.css\">\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        escape(title),
+        root,
+        body
+    )
+}
+
+fn note_list(notes: &[&Note], root: &str, date_format: &str, id: Option<&str>) -> String {
+    let items: String = notes.iter()
+        .map(|note| format!(
+            "<li><a href=\"{}notes/{}\">{}</a> {} <span class=\"date\">{}</span></li>\n",
+            root,
+            page_name(note),
+            escape(&title(note)),
+            badges(note),
+            note.created_at.format(date_format)
+        ))
+        .collect();
+    
+    match id {
+        Some(id) => format!("<ul id=\"{}\" class=\"notes\">\n{}</ul>", id, items),
+        None => format!("<ul class=\"notes\">\n{}</ul>", items),
+    }
+}
+
+fn badges(note: &Note) -> String {
+    let priority = note.priority.to_string();
+    let mut badges = format!("<span class=\"badge {}\">{}</span>", priority, priority);
+    if note.is_archived {
+        badges.push_str(" <span class=\"badge archived\">archived</span>");
+    }
+    badges
+}
+
+fn render_note(
+    note: &Note,
+    note_pages: &HashMap<i64, String>,
+    tag_pages: &HashMap<&str, String>,
+    date_format: &str,
+) -> String {
+    let mut meta = vec![
+        badges(note),
+        format!("Created {}", note.created_at.format(date_format)),
+    ];
+    if note.updated_at != note.created_at {
+        meta.push(format!("Updated {}", note.updated_at.format(date_format)));
+    }
+    if let Some(notebook) = &note.notebook {
+        meta.push(format!("Notebook: {}", escape(notebook)));
+    }
+    
+    let tags = note.tags.iter()
+        .map(|tag| format!("<a href=\"../tags/{}\">#{}</a>", tag_pages[tag.as_str()], escape(tag)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    format!(
+        "<nav><a href=\"../index.html\">&larr; All notes</a></nav>\n<article>\n<h1>{}</h1>\n\
+         <p class=\"meta\">{}</p>\n<p class=\"tags\">{}</p>\n<div class=\"content\">{}</div>\n</article>",
+        escape(&title(note)),
+        meta.join(" &middot; "),
+        tags,
+        link_notes(&escape(&note.content), note_pages)
+    )
+}
+
+/// Turn `[text](ezn:ID)` links into links to the note's page when it is part
+/// of the export. Runs on escaped content, which leaves brackets alone.
+fn link_notes(content: &str, note_pages: &HashMap<i64, String>) -> String {
+    let marker = format!("]({}", LINK_SCHEME);
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    
+    while let Some(pos) = rest.find(&marker) {
+        let (before, after) = (&rest[..pos], &rest[pos + marker.len()..]);
+        let digits = after.find(|c: char| !c.is_ascii_digit()).unwrap_or(after.len());
+        let target = after[..digits].parse::<i64>().ok().and_then(|id| note_pages.get(&id));
+        let open = before.rfind('[').filter(|&open| !before[open..].contains('\n'));
+        
+        match (target, open) {
+            (Some(target), Some(open)) if after[digits..].starts_with(')') => {
+                out.push_str(&before[..open]);
+                out.push_str(&format!("<a href=\"{}\">{}</a>", target, &before[open + 1..]));
+                rest = &after[digits + 1..];
+            }
+            _ => {
+                out.push_str(before);
+                out.push_str(&marker);
+                rest = after;
+            }
+        }
+    }
+    
+    out.push_str(rest);
+    out
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
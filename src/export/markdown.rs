@@ -0,0 +1,38 @@
+use std::path::{Path, PathBuf};
+
+use crate::models::Note;
+
+use super::file_stem;
+
+/// Write each note to `<dir>/<id>-<slug>.md`. Returns the files written.
+pub fn write_notes(notes: &[Note], dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    
+    let mut written = Vec::new();
+    for note in notes {
+        let path = dir.join(format!("{}.md", file_stem(note)));
+        std::fs::write(&path, render(note)?)?;
+        written.push(path);
+    }
+    
+    Ok(written)
+}
+
+/// The note's content preceded by YAML front matter. Strings are written as
+/// JSON literals, which YAML reads as double-quoted scalars.
+fn render(note: &Note) -> anyhow::Result<String> {
+    let mut out = String::from("---\n");
+    out.push_str(&format!("id: {}\n", note.id.unwrap_or_default()));
+    out.push_str(&format!("priority: {}\n", note.priority.to_string()));
+    out.push_str(&format!("tags: {}\n", serde_json::to_string(&note.tags)?));
+    if let Some(notebook) = &note.notebook {
+        out.push_str(&format!("notebook: {}\n", serde_json::to_string(notebook)?));
+    }
+    out.push_str(&format!("created_at: {}\n", note.created_at.to_rfc3339()));
+    out.push_str(&format!("updated_at: {}\n", note.updated_at.to_rfc3339()));
+    out.push_str(&format!("archived: {}\n", note.is_archived));
+    out.push_str("---\n\n");
+    out.push_str(&note.content);
+    out.push('\n');
+    Ok(out)
+}
@@ -0,0 +1,46 @@
+//! Writing notes out in formats other tools can read.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::Note;
+use crate::utils::slugify;
+
+pub mod csv;
+pub mod html;
+pub mod markdown;
+pub mod org;
+pub mod todotxt;
+
+/// Version of the JSON export layout, bumped whenever its fields change meaning.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The document written by `ezn export json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonExport {
+    pub schema_version: u32,
+    #[serde(default = "Utc::now")]
+    pub exported_at: DateTime<Utc>,
+    pub notes: Vec<Note>,
+}
+
+impl JsonExport {
+    pub fn new(notes: Vec<Note>) -> Self {
+        JsonExport {
+            schema_version: SCHEMA_VERSION,
+            exported_at: Utc::now(),
+            notes,
+        }
+    }
+}
+
+/// `<id>-<slug of the first line>`, used to name per-note files.
+fn file_stem(note: &Note) -> String {
+    let id = note.id.unwrap_or_default();
+    let slug = slugify(note.content.lines().next().unwrap_or(""));
+    if slug.is_empty() {
+        id.to_string()
+    } else {
+        format!("{}-{}", id, slug)
+    }
+}
@@ -0,0 +1,74 @@
+use std::io::Write;
+
+use crate::import::org::split_tags;
+use crate::models::Note;
+
+/// Write notes as an Org file with one top-level heading per note. The
+/// first line of the note is the heading, archived notes are `DONE` and the
+/// rest `TODO`, priorities are `[#A]` (urgent) to `[#D]` (low), and the ID,
+/// timestamps and notebook go in a `:PROPERTIES:` drawer. Tags are also kept
+/// exactly in an `:EZN_TAGS:` property whenever the heading alone would lose
+/// them: when Org can't represent one, when one is Org's special `ARCHIVE`,
+/// or when the first line itself ends in something that reads as tags.
+pub fn write_notes(notes: &[Note], mut out: impl Write) -> anyhow::Result<()> {
+    // Org only knows A-C unless told otherwise: highest A, lowest D, default C
+    writeln!(out, "#+PRIORITIES: A D C")?;
+    
+    for note in notes {
+        writeln!(out)?;
+        write_note(note, &mut out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_note(note: &Note, out: &mut impl Write) -> anyhow::Result<()> {
+    let mut lines = note.content.lines();
+    let title = lines.next().unwrap_or("");
+    
+    let mut heading = format!(
+        "* {} [#{}] {}",
+        if note.is_archived { "DONE" } else { "TODO" },
+        note.priority.letter(),
+        title
+    );
+    let org_tags: Vec<String> = note.tags.iter().map(|tag| org_tag(tag)).collect();
+    if !org_tags.is_empty() {
+        heading = format!("{:<60} :{}:", heading, org_tags.join(":"));
+    }
+    writeln!(out, "{}", heading.trim_end())?;
+    
+    writeln!(out, ":PROPERTIES:")?;
+    writeln!(out, ":ID:       {}", note.id.unwrap_or_default())?;
+    writeln!(out, ":CREATED:  {}", note.created_at.to_rfc3339())?;
+    writeln!(out, ":UPDATED:  {}", note.updated_at.to_rfc3339())?;
+    if let Some(notebook) = &note.notebook {
+        writeln!(out, ":NOTEBOOK: {}", notebook)?;
+    }
+    let ambiguous = org_tags != note.tags
+        || note.tags.iter().any(|tag| tag == "ARCHIVE")
+        || !split_tags(title).1.is_empty();
+    if ambiguous {
+        writeln!(out, ":EZN_TAGS: {}", serde_json::to_string(&note.tags)?)?;
+    }
+    writeln!(out, ":END:")?;
+    
+    for line in lines {
+        // A leading `*` would start a new heading; Org escapes it with a comma,
+        // and adds one more to lines that already start with commas and a `*`
+        if line.trim_start_matches(',').starts_with('*') {
+            writeln!(out, ",{}", line)?;
+        } else {
+            writeln!(out, "{}", line)?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Org tags may only contain letters, digits, `_`, `@`, `#` and `%`.
+fn org_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%') { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,48 @@
+use std::io::Write;
+
+use crate::models::{Note, Priority};
+
+/// Write one todo.txt task per note. Medium notes get no priority, so plain
+/// tasks stay plain. Archived notes are written as completed (`x`), with their
+/// priority kept as a `pri:` tag as the format recommends.
+/// Tags starting with `@` are written as contexts and all others as `+projects`.
+pub fn write_notes(notes: &[Note], mut out: impl Write) -> anyhow::Result<()> {
+    for note in notes {
+        writeln!(out, "{}", task_line(note))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn task_line(note: &Note) -> String {
+    let mut words = Vec::new();
+    let created = note.created_at.format("%Y-%m-%d").to_string();
+    
+    if note.is_archived {
+        words.push("x".to_string());
+        words.push(note.updated_at.format("%Y-%m-%d").to_string());
+        words.push(created);
+    } else {
+        if note.priority != Priority::Medium {
+            words.push(format!("({})", note.priority.letter()));
+        }
+        words.push(created);
+    }
+    
+    // todo.txt has no multi-line tasks
+    words.extend(note.content.split_whitespace().map(str::to_string));
+    
+    for tag in &note.tags {
+        if tag.starts_with('@') {
+            words.push(tag.clone());
+        } else {
+            words.push(format!("+{}", tag));
+        }
+    }
+    
+    if note.is_archived && note.priority != Priority::Medium {
+        words.push(format!("pri:{}", note.priority.letter()));
+    }
+    
+    words.join(" ")
+}
@@ -0,0 +1,499 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use roxmltree::{Document, Node, ParsingOptions};
+
+use crate::models::{Note, Priority};
+
+use super::Batch;
+
+/// The HTML 4 named entities, which Evernote writes into ENML but XML
+/// doesn't define, with their code points. XML's own `&amp;`, `&lt;`, `&gt;`
+/// and `&quot;` are left out, since the parser handles those.
+const HTML_ENTITIES: &[(&str, u32)] = &[
+    ("nbsp", 160), ("iexcl", 161), ("cent", 162), ("pound", 163), ("curren", 164),
+    ("yen", 165), ("brvbar", 166), ("sect", 167), ("uml", 168), ("copy", 169),
+    ("ordf", 170), ("laquo", 171), ("not", 172), ("shy", 173), ("reg", 174),
+    ("macr", 175), ("deg", 176), ("plusmn", 177), ("sup2", 178), ("sup3", 179),
+    ("acute", 180), ("micro", 181), ("para", 182), ("middot", 183), ("cedil", 184),
+    ("sup1", 185), ("ordm", 186), ("raquo", 187), ("frac14", 188), ("frac12", 189),
+    ("frac34", 190), ("iquest", 191), ("Agrave", 192), ("Aacute", 193),
+    ("Acirc", 194), ("Atilde", 195), ("Auml", 196), ("Aring", 197), ("AElig", 198),
+    ("Ccedil", 199), ("Egrave", 200), ("Eacute", 201), ("Ecirc", 202), ("Euml", 203),
+    ("Igrave", 204), ("Iacute", 205), ("Icirc", 206), ("Iuml", 207), ("ETH", 208),
+    ("Ntilde", 209), ("Ograve", 210), ("Oacute", 211), ("Ocirc", 212),
+    ("Otilde", 213), ("Ouml", 214), ("times", 215), ("Oslash", 216), ("Ugrave", 217),
+    ("Uacute", 218), ("Ucirc", 219), ("Uuml", 220), ("Yacute", 221), ("THORN", 222),
+    ("szlig", 223), ("agrave", 224), ("aacute", 225), ("acirc", 226),
+    ("atilde", 227), ("auml", 228), ("aring", 229), ("aelig", 230), ("ccedil", 231),
+    ("egrave", 232), ("eacute", 233), ("ecirc", 234), ("euml", 235), ("igrave", 236),
+    ("iacute", 237), ("icirc", 238), ("iuml", 239), ("eth", 240), ("ntilde", 241),
+    ("ograve", 242), ("oacute", 243), ("ocirc", 244), ("otilde", 245), ("ouml", 246),
+    ("divide", 247), ("oslash", 248), ("ugrave", 249), ("uacute", 250),
+    ("ucirc", 251), ("uuml", 252), ("yacute", 253), ("thorn", 254), ("yuml", 255),
+    ("OElig", 338), ("oelig", 339), ("Scaron", 352), ("scaron", 353), ("Yuml", 376),
+    ("fnof", 402), ("circ", 710), ("tilde", 732), ("Alpha", 913), ("Beta", 914),
+    ("Gamma", 915), ("Delta", 916), ("Epsilon", 917), ("Zeta", 918), ("Eta", 919),
+    ("Theta", 920), ("Iota", 921), ("Kappa", 922), ("Lambda", 923), ("Mu", 924),
+    ("Nu", 925), ("Xi", 926), ("Omicron", 927), ("Pi", 928), ("Rho", 929),
+    ("Sigma", 931), ("Tau", 932), ("Upsilon", 933), ("Phi", 934), ("Chi", 935),
+    ("Psi", 936), ("Omega", 937), ("alpha", 945), ("beta", 946), ("gamma", 947),
+    ("delta", 948), ("epsilon", 949), ("zeta", 950), ("eta", 951), ("theta", 952),
+    ("iota", 953), ("kappa", 954), ("lambda", 955), ("mu", 956), ("nu", 957),
+    ("xi", 958), ("omicron", 959), ("pi", 960), ("rho", 961), ("sigmaf", 962),
+    ("sigma", 963), ("tau", 964), ("upsilon", 965), ("phi", 966), ("chi", 967),
+    ("psi", 968), ("omega", 969), ("thetasym", 977), ("upsih", 978), ("piv", 982),
+    ("ensp", 8194), ("emsp", 8195), ("thinsp", 8201), ("zwnj", 8204), ("zwj", 8205),
+    ("lrm", 8206), ("rlm", 8207), ("ndash", 8211), ("mdash", 8212), ("lsquo", 8216),
+    ("rsquo", 8217), ("sbquo", 8218), ("ldquo", 8220), ("rdquo", 8221),
+    ("bdquo", 8222), ("dagger", 8224), ("Dagger", 8225), ("bull", 8226),
+    ("hellip", 8230), ("permil", 8240), ("prime", 8242), ("Prime", 8243),
+    ("lsaquo", 8249), ("rsaquo", 8250), ("oline", 8254), ("frasl", 8260),
+    ("euro", 8364), ("image", 8465), ("weierp", 8472), ("real", 8476),
+    ("trade", 8482), ("alefsym", 8501), ("larr", 8592), ("uarr", 8593),
+    ("rarr", 8594), ("darr", 8595), ("harr", 8596), ("crarr", 8629), ("lArr", 8656),
+    ("uArr", 8657), ("rArr", 8658), ("dArr", 8659), ("hArr", 8660), ("forall", 8704),
+    ("part", 8706), ("exist", 8707), ("empty", 8709), ("nabla", 8711),
+    ("isin", 8712), ("notin", 8713), ("ni", 8715), ("prod", 8719), ("sum", 8721),
+    ("minus", 8722), ("lowast", 8727), ("radic", 8730), ("prop", 8733),
+    ("infin", 8734), ("ang", 8736), ("and", 8743), ("or", 8744), ("cap", 8745),
+    ("cup", 8746), ("int", 8747), ("there4", 8756), ("sim", 8764), ("cong", 8773),
+    ("asymp", 8776), ("ne", 8800), ("equiv", 8801), ("le", 8804), ("ge", 8805),
+    ("sub", 8834), ("sup", 8835), ("nsub", 8836), ("sube", 8838), ("supe", 8839),
+    ("oplus", 8853), ("otimes", 8855), ("perp", 8869), ("sdot", 8901),
+    ("lceil", 8968), ("rceil", 8969), ("lfloor", 8970), ("rfloor", 8971),
+    ("lang", 9001), ("rang", 9002), ("loz", 9674), ("spades", 9824), ("clubs", 9827),
+    ("hearts", 9829), ("diams", 9830),
+];
+
+/// Read an Evernote `.enex` export. Each note's ENML body becomes Markdown
+/// under a `# Title` heading. Attachments are not imported; a placeholder
+/// marks where each one was.
+pub fn read_enex(text: &str) -> anyhow::Result<Batch> {
+    let document = Document::parse_with_options(text, dtd_allowed())
+        .map_err(|e| anyhow::anyhow!("Not a valid ENEX file: {}", e))?;
+    
+    let root = document.root_element();
+    if root.tag_name().name() != "en-export" {
+        return Err(anyhow::anyhow!("Not an ENEX file: expected <en-export>, found <{}>", root.tag_name().name()));
+    }
+    
+    let mut batch = Batch::default();
+    for (index, element) in root.children().filter(|n| n.has_tag_name("note")).enumerate() {
+        let title = child_text(element, "title").unwrap_or_default();
+        match read_note(element, &title) {
+            Ok(note) => batch.notes.push(note),
+            Err(e) => {
+                let label = if title.is_empty() { format!("note {}", index + 1) } else { format!("\"{}\"", title) };
+                batch.failures.push(format!("{}: {}", label, e));
+            }
+        }
+    }
+    
+    Ok(batch)
+}
+
+fn read_note(element: Node, title: &str) -> anyhow::Result<Note> {
+    let enml = child_text(element, "content").unwrap_or_default();
+    let body = enml_to_markdown(&enml)?;
+    
+    let content = match (title.trim(), body.is_empty()) {
+        ("", _) => body,
+        (title, true) => format!("# {}", title),
+        (title, false) => format!("# {}\n\n{}", title, body),
+    };
+    
+    let updated = child_text(element, "updated").as_deref().and_then(parse_timestamp);
+    let created_at = child_text(element, "created").as_deref()
+        .and_then(parse_timestamp)
+        .or(updated)
+        .unwrap_or_else(Utc::now);
+    
+    let tags = element.children()
+        .filter(|n| n.has_tag_name("tag"))
+        .filter_map(|n| n.text())
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    
+    Ok(Note {
+        id: None,
+        content,
+        priority: Priority::Medium,
+        created_at,
+        updated_at: updated.unwrap_or(created_at).max(created_at),
+        is_archived: false,
+        tags,
+        deleted_at: None,
+        notebook: None,
+    })
+}
+
+fn dtd_allowed() -> ParsingOptions {
+    ParsingOptions { allow_dtd: true, ..ParsingOptions::default() }
+}
+
+fn child_text(element: Node, name: &str) -> Option<String> {
+    element.children()
+        .find(|n| n.has_tag_name(name))
+        .map(|n| n.text().unwrap_or_default().to_string())
+}
+
+/// ENEX timestamps look like `20230401T093000Z`.
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(s.trim(), "%Y%m%dT%H%M%SZ").ok().map(|t| t.and_utc())
+}
+
+fn enml_to_markdown(enml: &str) -> anyhow::Result<String> {
+    // A note with only a title has no `<content>` at all
+    if enml.trim().is_empty() {
+        return Ok(String::new());
+    }
+    
+    let xml = replace_html_entities(enml);
+    let document = Document::parse_with_options(&xml, dtd_allowed())
+        .map_err(|e| anyhow::anyhow!("could not parse note body: {}", e))?;
+    
+    let mut writer = MarkdownWriter::default();
+    writer.render(document.root_element());
+    Ok(writer.finish())
+}
+
+/// Turn HTML named entities into numeric references XML understands. Any
+/// name that isn't known is kept as literal text rather than failing the note.
+fn replace_html_entities(enml: &str) -> String {
+    let mut out = String::with_capacity(enml.len());
+    let mut rest = enml;
+    
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        
+        let name = rest.find(';')
+            .map(|end| &rest[..end])
+            .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric()));
+        match name {
+            Some(name @ ("amp" | "lt" | "gt" | "quot" | "apos")) => out.push_str(&format!("&{};", name)),
+            Some(name) => match HTML_ENTITIES.iter().find(|(entity, _)| *entity == name) {
+                Some((_, code)) => out.push_str(&format!("&#{};", code)),
+                None => out.push_str(&format!("&amp;{};", name)),
+            },
+            // `&#233;` and the like pass through to the parser; a stray `&` is text
+            None => {
+                out.push_str(if rest.starts_with('#') { "&" } else { "&amp;" });
+                continue;
+            }
+        }
+        rest = &rest[name.map_or(0, str::len) + 1..];
+    }
+    
+    out.push_str(rest);
+    out
+}
+
+/// Renders ENML (a subset of XHTML) as Markdown.
+#[derive(Default)]
+struct MarkdownWriter {
+    out: String,
+    /// One entry per open list: `None` for bullets, or the next number
+    lists: Vec<Option<usize>>,
+}
+
+impl MarkdownWriter {
+    fn render(&mut self, node: Node) {
+        if node.is_text() {
+            let text = node.text().unwrap_or_default();
+            let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if collapsed.is_empty() {
+                if !text.is_empty() && !self.at_line_start() {
+                    self.out.push(' ');
+                }
+                return;
+            }
+            if text.starts_with(char::is_whitespace) && !self.at_line_start() {
+                self.out.push(' ');
+            }
+            self.out.push_str(&collapsed);
+            if text.ends_with(char::is_whitespace) {
+                self.out.push(' ');
+            }
+            return;
+        }
+        if !node.is_element() {
+            return;
+        }
+        
+        match node.tag_name().name() {
+            "br" => self.out.push('\n'),
+            "div" => {
+                self.end_line();
+                self.children(node);
+                self.end_line();
+            }
+            "p" => {
+                self.end_block();
+                self.children(node);
+                self.end_block();
+            }
+            name @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                let level = name[1..].parse().unwrap_or(1);
+                self.end_block();
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+                self.children(node);
+                self.end_block();
+            }
+            list @ ("ul" | "ol") => {
+                self.end_line();
+                self.lists.push(if list == "ol" { Some(1) } else { None });
+                self.children(node);
+                self.lists.pop();
+                self.end_line();
+            }
+            "li" => {
+                self.end_line();
+                let depth = self.lists.len().saturating_sub(1);
+                self.out.push_str(&"  ".repeat(depth));
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                self.out.push_str(&marker);
+                self.children(node);
+                self.end_line();
+            }
+            "b" | "strong" => self.wrap(node, "**"),
+            "i" | "em" => self.wrap(node, "*"),
+            "s" | "strike" | "del" => self.wrap(node, "~~"),
+            "code" => self.wrap(node, "`"),
+            "pre" => {
+                self.end_block();
+                self.out.push_str("```\n");
+                self.out.push_str(node_text(node).trim_end());
+                self.out.push_str("\n```");
+                self.end_block();
+            }
+            "blockquote" => {
+                let mut inner = MarkdownWriter::default();
+                inner.children(node);
+                self.end_block();
+                for line in inner.finish().lines() {
+                    self.out.push_str(&format!("> {}\n", line).replace("> \n", ">\n"));
+                }
+                self.end_block();
+            }
+            "a" => {
+                let href = node.attribute("href").unwrap_or_default();
+                let text = node_text(node);
+                if href.is_empty() || text.trim() == href {
+                    self.out.push_str(if href.is_empty() { text.trim() } else { href });
+                } else {
+                    self.out.push_str(&format!("[{}]({})", text.trim(), href));
+                }
+            }
+            "img" => {
+                let alt = node.attribute("alt").unwrap_or_default();
+                let src = node.attribute("src").unwrap_or_default();
+                self.out.push_str(&format!("![{}]({})", alt, src));
+            }
+            "hr" => {
+                self.end_block();
+                self.out.push_str("---");
+                self.end_block();
+            }
+            "table" => {
+                self.end_block();
+                let rows = node.children()
+                    .flat_map(|child| match child.tag_name().name() {
+                        "thead" | "tbody" | "tfoot" => child.children().collect(),
+                        _ => vec![child],
+                    })
+                    .filter(|n| n.has_tag_name("tr"));
+                for (index, row) in rows.enumerate() {
+                    let cells: Vec<String> = row.children()
+                        .filter(|n| n.has_tag_name("td") || n.has_tag_name("th"))
+                        .map(|cell| {
+                            let mut inner = MarkdownWriter::default();
+                            inner.children(cell);
+                            inner.finish().replace('\n', " ")
+                        })
+                        .collect();
+                    self.out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                    // Markdown takes the first row as the header, and needs a separator under it
+                    if index == 0 {
+                        self.out.push_str(&format!("|{}\n", " --- |".repeat(cells.len().max(1))));
+                    }
+                }
+                self.end_block();
+            }
+            "en-todo" => {
+                let checked = node.attribute("checked") == Some("true");
+                self.out.push_str(if checked { "[x] " } else { "[ ] " });
+            }
+            "en-media" => {
+                let kind = node.attribute("type").unwrap_or("file");
+                self.out.push_str(&format!("[attachment: {}]", kind));
+            }
+            "en-crypt" => self.out.push_str("[encrypted content]"),
+            _ => self.children(node),
+        }
+    }
+    
+    fn children(&mut self, node: Node) {
+        for child in node.children() {
+            self.render(child);
+        }
+    }
+    
+    fn wrap(&mut self, node: Node, marker: &str) {
+        let text = node_text(node);
+        if text.trim().is_empty() {
+            return;
+        }
+        self.out.push_str(&format!("{}{}{}", marker, text.trim(), marker));
+    }
+    
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+    
+    fn end_line(&mut self) {
+        self.trim_trailing_spaces();
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+    
+    fn end_block(&mut self) {
+        self.end_line();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+    
+    fn trim_trailing_spaces(&mut self) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+    }
+    
+    fn finish(self) -> String {
+        let mut text = String::new();
+        let mut blank_lines = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank_lines += 1;
+                if blank_lines > 1 {
+                    continue;
+                }
+            } else {
+                blank_lines = 0;
+            }
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.trim().to_string()
+    }
+}
+
+/// All text inside `node`, with whitespace collapsed outside `<pre>`.
+fn node_text(node: Node) -> String {
+    let raw: String = node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect();
+    
+    if node.has_tag_name("pre") {
+        raw
+    } else {
+        raw.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn body(html: &str) -> String {
+        let enml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+             <!DOCTYPE en-note SYSTEM \"http://xml.evernote.com/pub/enml2.dtd\"><en-note>{}</en-note>",
+            html
+        );
+        enml_to_markdown(&enml).unwrap()
+    }
+    
+    #[test]
+    fn decodes_html_entities() {
+        assert_eq!(body("<div>caf&eacute; &euro;5 &times; 2 &laquo;ok&raquo;&nbsp;&hellip;</div>"), "café €5 × 2 «ok» …");
+        assert_eq!(body("<div>&lt;b&gt; &amp; &quot;x&quot; &#233; &#x263A;</div>"), "<b> & \"x\" é ☺");
+    }
+    
+    #[test]
+    fn keeps_unknown_entities_and_stray_ampersands_as_text() {
+        assert_eq!(body("<div>&notanentity; AT&T; R&D</div>"), "&notanentity; AT&T; R&D");
+    }
+    
+    #[test]
+    fn reads_notes_with_tags_and_timestamps() {
+        let enex = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export3.dtd">
+<en-export application="Evernote" version="10.0">
+  <note>
+    <title>Groceries</title>
+    <content><![CDATA[<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-note SYSTEM "http://xml.evernote.com/pub/enml2.dtd">
+<en-note><div>For <b>Saturday</b>:</div><ul><li><en-todo checked="true"/>milk</li><li><en-todo/>eggs</li></ul><ol><li>shop</li><li>cook</li></ol><en-media type="image/png" hash="abc"/></en-note>]]></content>
+    <created>20230401T093000Z</created>
+    <updated>20230402T100000Z</updated>
+    <tag>home</tag>
+    <tag> errands </tag>
+  </note>
+  <note>
+    <title>Broken</title>
+    <content><![CDATA[<en-note><div>unclosed</en-note>]]></content>
+  </note>
+  <note>
+    <title>Empty</title>
+    <created>20230105T080000Z</created>
+  </note>
+</en-export>"#;
+        let batch = read_enex(enex).unwrap();
+        
+        assert_eq!(batch.notes.len(), 2, "{:?}", batch.failures);
+        let note = &batch.notes[0];
+        assert_eq!(
+            note.content,
+            "# Groceries\n\nFor **Saturday**:\n- [x] milk\n- [ ] eggs\n1. shop\n2. cook\n[attachment: image/png]"
+        );
+        assert_eq!(note.tags, vec!["home", "errands"]);
+        assert_eq!(note.created_at.to_rfc3339(), "2023-04-01T09:30:00+00:00");
+        assert_eq!(note.updated_at.to_rfc3339(), "2023-04-02T10:00:00+00:00");
+        
+        assert_eq!(batch.notes[1].content, "# Empty");
+        assert_eq!(batch.notes[1].updated_at, batch.notes[1].created_at);
+        
+        assert_eq!(batch.failures.len(), 1);
+        assert!(batch.failures[0].starts_with("\"Broken\": could not parse note body"), "{}", batch.failures[0]);
+    }
+    
+    #[test]
+    fn converts_formatting_to_markdown() {
+        assert_eq!(
+            body("<h2>Plan</h2><p>See <a href=\"https://example.com\">the site</a> and <code>make</code>.</p><pre>fn main() {\n    run();\n}</pre><blockquote><div>quoted</div></blockquote><hr/><table><tr><td>a</td><td><i>b</i></td></tr></table>"),
+            "## Plan\n\nSee [the site](https://example.com) and `make`.\n\n```\nfn main() {\n    run();\n}\n```\n\n> quoted\n\n---\n\n| a | *b* |\n| --- | --- |"
+        );
+        assert_eq!(
+            body("<div>Totals</div><table><thead><tr><th>Item</th><th>Cost</th></tr></thead><tbody><tr><td>Tent</td><td>120</td></tr><tr><td>Stove</td><td>45</td></tr></tbody></table><div>Done</div>"),
+            "Totals\n\n| Item | Cost |\n| --- | --- |\n| Tent | 120 |\n| Stove | 45 |\n\nDone"
+        );
+    }
+    
+    #[test]
+    fn rejects_files_that_are_not_enex() {
+        let error = read_enex("<html><body/></html>").unwrap_err().to_string();
+        assert_eq!(error, "Not an ENEX file: expected <en-export>, found <html>");
+        assert!(read_enex("not xml").unwrap_err().to_string().starts_with("Not a valid ENEX file"));
+    }
+}
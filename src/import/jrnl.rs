@@ -0,0 +1,150 @@
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::models::{Note, Priority};
+
+use super::Batch;
+
+/// The document written by `jrnl --export json`.
+#[derive(Deserialize)]
+struct Journal {
+    entries: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    body: String,
+    date: String,
+    time: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    starred: bool,
+}
+
+/// Parse a `jrnl --export json` document. Each entry's title and body become
+/// the note text, `@tags` become tags and starred entries become high priority.
+/// jrnl writes wall-clock times, so they're read in the local time zone.
+pub fn parse_export(text: &str) -> anyhow::Result<Batch> {
+    parse_export_in(text, &Local)
+}
+
+fn parse_export_in<Tz: TimeZone>(text: &str, tz: &Tz) -> anyhow::Result<Batch> {
+    let journal: Journal = serde_json::from_str(text)
+        .map_err(|e| anyhow::anyhow!("Not a jrnl JSON export: {}", e))?;
+    
+    let mut batch = Batch::default();
+    for (index, value) in journal.entries.into_iter().enumerate() {
+        let note = serde_json::from_value::<Entry>(value)
+            .map_err(|e| e.to_string())
+            .and_then(|entry| to_note(entry, tz));
+        match note {
+            Ok(note) => batch.notes.push(note),
+            Err(e) => batch.failures.push(format!("entry {}: {}", index + 1, e)),
+        }
+    }
+    
+    Ok(batch)
+}
+
+fn to_note<Tz: TimeZone>(entry: Entry, tz: &Tz) -> Result<Note, String> {
+    let date = NaiveDate::parse_from_str(entry.date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}'", entry.date))?;
+    let time = match entry.time.as_deref().map(str::trim) {
+        Some(time) if !time.is_empty() => NaiveTime::parse_from_str(time, "%H:%M")
+            .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+            .map_err(|_| format!("invalid time '{}'", time))?,
+        _ => NaiveTime::MIN,
+    };
+    // A time skipped by a daylight saving change doesn't exist; a repeated one is read as the first
+    let created_at = tz.from_local_datetime(&date.and_time(time))
+        .earliest()
+        .ok_or_else(|| format!("time '{} {}' doesn't exist in the local time zone", date, time))?
+        .with_timezone(&Utc);
+    
+    let content = match (entry.title.trim(), entry.body.trim()) {
+        (title, "") => title.to_string(),
+        ("", body) => body.to_string(),
+        (title, body) => format!("{}\n\n{}", title, body),
+    };
+    
+    let mut tags = Vec::new();
+    for tag in entry.tags {
+        // jrnl keeps the tag symbol (`@` by default) in the tag name
+        let tag = tag.trim_start_matches(['@', '#']).to_string();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    
+    Ok(Note {
+        id: None,
+        content,
+        priority: if entry.starred { Priority::High } else { Priority::Medium },
+        created_at,
+        updated_at: created_at,
+        is_archived: false,
+        tags,
+        deleted_at: None,
+        notebook: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    
+    #[test]
+    fn reads_a_jrnl_export() {
+        let batch = parse_export_in(r##"{
+            "tags": {"@work": 1},
+            "entries": [
+                {"title": "Shipped the release.", "body": " Long day. @work\n", "date": "2024-03-01", "time": "18:30",
+                 "tags": ["@work", "#work", "@team"], "starred": true},
+                {"title": "", "body": "Just a body", "date": "2024-03-02", "time": "07:05:09", "tags": [], "starred": false},
+                {"title": "Undated", "body": "", "date": "someday", "time": "09:00"},
+                {"title": "Bad time", "body": "", "date": "2024-03-03", "time": "noon"},
+                {"title": "Midnight", "date": "2024-03-04"}
+            ]
+        }"##, &Utc).unwrap();
+        
+        assert_eq!(batch.failures, vec!["entry 3: invalid date 'someday'", "entry 4: invalid time 'noon'"]);
+        let [shipped, body, midnight] = batch.notes.as_slice() else {
+            panic!("expected three entries, got {:?}", batch.notes);
+        };
+        
+        assert_eq!(shipped.content, "Shipped the release.\n\nLong day. @work");
+        assert_eq!(shipped.tags, vec!["work", "team"]);
+        assert_eq!(shipped.priority, Priority::High);
+        assert_eq!(shipped.created_at.to_rfc3339(), "2024-03-01T18:30:00+00:00");
+        
+        assert_eq!(body.content, "Just a body");
+        assert_eq!(body.priority, Priority::Medium);
+        assert_eq!(body.created_at.to_rfc3339(), "2024-03-02T07:05:09+00:00");
+        
+        assert_eq!(midnight.content, "Midnight");
+        assert_eq!(midnight.created_at.to_rfc3339(), "2024-03-04T00:00:00+00:00");
+    }
+    
+    #[test]
+    fn reads_times_as_local_time() {
+        let export = r#"{"entries": [{"title": "Late", "date": "2024-03-01", "time": "01:30"}]}"#;
+        let berlin = FixedOffset::east_opt(3600).unwrap();
+        let batch = parse_export_in(export, &berlin).unwrap();
+        assert_eq!(batch.notes[0].created_at.to_rfc3339(), "2024-03-01T00:30:00+00:00");
+        
+        let new_york = FixedOffset::west_opt(5 * 3600).unwrap();
+        let batch = parse_export_in(export, &new_york).unwrap();
+        assert_eq!(batch.notes[0].created_at.to_rfc3339(), "2024-03-01T06:30:00+00:00");
+    }
+    
+    #[test]
+    fn rejects_other_json() {
+        let error = parse_export("[1, 2]").unwrap_err().to_string();
+        assert!(error.starts_with("Not a jrnl JSON export"), "{}", error);
+    }
+}
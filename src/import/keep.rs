@@ -0,0 +1,240 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::models::{Note, Priority};
+
+use super::Batch;
+
+/// One note as written by Google Takeout, e.g. `Takeout/Keep/Groceries.json`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeepNote {
+    #[serde(default)]
+    title: String,
+    text_content: Option<String>,
+    list_content: Option<Vec<KeepListItem>>,
+    #[serde(default)]
+    labels: Vec<KeepLabel>,
+    #[serde(default)]
+    annotations: Vec<KeepAnnotation>,
+    #[serde(default)]
+    attachments: Vec<KeepAttachment>,
+    #[serde(default)]
+    is_pinned: bool,
+    #[serde(default)]
+    is_archived: bool,
+    #[serde(default)]
+    is_trashed: bool,
+    created_timestamp_usec: Option<i64>,
+    user_edited_timestamp_usec: Option<i64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeepListItem {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    is_checked: bool,
+}
+
+#[derive(Deserialize)]
+struct KeepLabel {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct KeepAnnotation {
+    title: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KeepAttachment {
+    mimetype: Option<String>,
+}
+
+/// Keys only Keep writes, which tell its notes apart from the other JSON in a
+/// Takeout export (Photos metadata, Chrome settings and so on).
+const KEEP_KEYS: &[&str] = &[
+    "textContent",
+    "listContent",
+    "isPinned",
+    "isArchived",
+    "isTrashed",
+    "userEditedTimestampUsec",
+    "createdTimestampUsec",
+];
+
+/// Read every Keep note `.json` file under a Takeout directory, or under its
+/// `Keep` folder when it has one. Other JSON files are skipped. Pinned notes
+/// become high priority, and archived or trashed notes are imported archived.
+pub fn read_takeout(dir: &Path) -> anyhow::Result<Batch> {
+    if !dir.is_dir() {
+        return Err(anyhow::anyhow!("{} is not a directory", dir.display()));
+    }
+    let keep_dir = dir.join("Keep");
+    let dir = if keep_dir.is_dir() { keep_dir.as_path() } else { dir };
+    
+    let mut files = Vec::new();
+    collect_json_files(dir, &mut files)?;
+    files.sort();
+    
+    let mut batch = Batch::default();
+    for path in files {
+        match read_note(&path) {
+            Ok(Some(note)) => batch.notes.push(note),
+            Ok(None) => {}
+            Err(e) => batch.failures.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+    
+    Ok(batch)
+}
+
+fn collect_json_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_json_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json")) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The note in a Keep `.json` file, or `None` for JSON that isn't Keep's.
+fn read_note(path: &Path) -> anyhow::Result<Option<Note>> {
+    let text = std::fs::read_to_string(path)?;
+    let value: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| anyhow::anyhow!("invalid JSON: {}", e))?;
+    if !value.as_object().is_some_and(|object| KEEP_KEYS.iter().any(|key| object.contains_key(*key))) {
+        return Ok(None);
+    }
+    let keep: KeepNote = serde_json::from_value(value)
+        .map_err(|e| anyhow::anyhow!("not a Keep note: {}", e))?;
+    
+    if keep.text_content.is_none() && keep.list_content.is_none() {
+        return Err(anyhow::anyhow!("not a Keep note: no textContent or listContent"));
+    }
+    
+    let updated_at = keep.user_edited_timestamp_usec
+        .and_then(from_usec)
+        .unwrap_or_else(Utc::now);
+    let created_at = keep.created_timestamp_usec
+        .and_then(from_usec)
+        .unwrap_or(updated_at);
+    
+    Ok(Some(Note {
+        id: None,
+        content: render(&keep),
+        priority: if keep.is_pinned { Priority::High } else { Priority::Medium },
+        created_at,
+        updated_at: updated_at.max(created_at),
+        is_archived: keep.is_archived || keep.is_trashed,
+        tags: keep.labels.into_iter().map(|label| label.name).collect(),
+        deleted_at: None,
+        notebook: None,
+    }))
+}
+
+fn from_usec(usec: i64) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp_micros(usec)
+}
+
+/// The note as Markdown: title as a heading, then the text or checklist,
+/// then any links and attachment placeholders.
+fn render(keep: &KeepNote) -> String {
+    let mut blocks = Vec::new();
+    
+    if !keep.title.trim().is_empty() {
+        blocks.push(format!("# {}", keep.title.trim()));
+    }
+    
+    if let Some(text) = keep.text_content.as_deref().filter(|t| !t.trim().is_empty()) {
+        blocks.push(text.trim_end().to_string());
+    }
+    
+    if let Some(items) = &keep.list_content {
+        let list = items.iter()
+            .map(|item| format!("- [{}] {}", if item.is_checked { "x" } else { " " }, item.text.trim()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !list.is_empty() {
+            blocks.push(list);
+        }
+    }
+    
+    let links = keep.annotations.iter()
+        .filter_map(|a| {
+            let url = a.url.as_deref()?;
+            Some(match a.title.as_deref().filter(|t| !t.is_empty()) {
+                Some(title) => format!("- [{}]({})", title, url),
+                None => format!("- <{}>", url),
+            })
+        })
+        .collect::<Vec<_>>();
+    if !links.is_empty() {
+        blocks.push(links.join("\n"));
+    }
+    
+    for attachment in &keep.attachments {
+        blocks.push(format!("[attachment: {}]", attachment.mimetype.as_deref().unwrap_or("file")));
+    }
+    
+    blocks.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const NOTE: &str = r#"{
+        "title": "Groceries",
+        "listContent": [{"text": "milk", "isChecked": true}, {"text": "eggs", "isChecked": false}],
+        "labels": [{"name": "home"}],
+        "isPinned": true,
+        "isArchived": false,
+        "isTrashed": false,
+        "createdTimestampUsec": 1709285400000000,
+        "userEditedTimestampUsec": 1709289000000000
+    }"#;
+    
+    #[test]
+    fn reads_only_the_keep_folder_of_a_takeout() {
+        let takeout = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(takeout.path().join("Keep")).unwrap();
+        std::fs::create_dir_all(takeout.path().join("Google Photos")).unwrap();
+        std::fs::write(takeout.path().join("Keep/Groceries.json"), NOTE).unwrap();
+        std::fs::write(takeout.path().join("Google Photos/IMG_1.jpg.json"), r#"{"title": "IMG_1.jpg"}"#).unwrap();
+        std::fs::write(takeout.path().join("Google Photos/broken.json"), "{").unwrap();
+        
+        let batch = read_takeout(takeout.path()).unwrap();
+        
+        assert!(batch.failures.is_empty(), "{:?}", batch.failures);
+        assert_eq!(batch.notes.len(), 1);
+        let note = &batch.notes[0];
+        assert_eq!(note.content, "# Groceries\n\n- [x] milk\n- [ ] eggs");
+        assert_eq!(note.tags, vec!["home"]);
+        assert_eq!(note.priority, Priority::High);
+        assert_eq!(note.created_at.to_rfc3339(), "2024-03-01T09:30:00+00:00");
+        assert_eq!(note.updated_at.to_rfc3339(), "2024-03-01T10:30:00+00:00");
+    }
+    
+    #[test]
+    fn skips_other_json_without_reporting_it() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("note.json"), NOTE).unwrap();
+        std::fs::write(dir.path().join("photo.json"), r#"{"title": "IMG_1.jpg", "photoTakenTime": {}}"#).unwrap();
+        std::fs::write(dir.path().join("list.json"), "[1, 2]").unwrap();
+        std::fs::write(dir.path().join("empty.json"), r#"{"title": "x", "isPinned": false}"#).unwrap();
+        
+        let batch = read_takeout(dir.path()).unwrap();
+        
+        assert_eq!(batch.notes.len(), 1);
+        assert_eq!(batch.failures.len(), 1);
+        assert!(batch.failures[0].ends_with("empty.json: not a Keep note: no textContent or listContent"));
+    }
+}
@@ -0,0 +1,37 @@
+//! Reading notes written by ezn or other tools into `Note`s ready for
+//! `NoteService::import_notes`.
+
+use crate::export::{JsonExport, SCHEMA_VERSION};
+use crate::models::Note;
+
+pub mod enex;
+pub mod jrnl;
+pub mod keep;
+pub mod obsidian;
+pub mod org;
+pub mod taskwarrior;
+pub mod todotxt;
+
+/// Notes read from another tool, plus a description of each entry that
+/// couldn't be converted.
+#[derive(Debug, Default)]
+pub struct Batch {
+    pub notes: Vec<Note>,
+    pub failures: Vec<String>,
+}
+
+/// Parse a document written by `ezn export json`.
+pub fn parse_json(text: &str) -> anyhow::Result<Vec<Note>> {
+    let document: JsonExport = serde_json::from_str(text)
+        .map_err(|e| anyhow::anyhow!("Not an ezn JSON export: {}", e))?;
+    
+    if document.schema_version > SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "Export uses schema version {}, but this ezn only understands up to {}. Please upgrade ezn.",
+            document.schema_version,
+            SCHEMA_VERSION
+        ));
+    }
+    
+    Ok(document.notes)
+}
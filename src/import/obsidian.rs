@@ -0,0 +1,438 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::models::{Note, Priority, LINK_SCHEME};
+
+use super::Batch;
+
+/// Read every `.md` file under `dir` (skipping hidden folders such as
+/// `.obsidian`) into notes. A note keeps the `id` from its front matter, as
+/// written by `ezn export`, so existing `ezn:` links still find it; others get
+/// negative provisional IDs that can't clash with those. Either way,
+/// `[[wikilinks]]` between notes become `ezn:` links that the import rewrites.
+/// Files that can't be read are reported rather than stopping the import.
+pub fn read_vault(dir: &Path) -> anyhow::Result<Batch> {
+    if !dir.is_dir() {
+        return Err(anyhow::anyhow!("{} is not a directory", dir.display()));
+    }
+    
+    let mut files = Vec::new();
+    collect_markdown_files(dir, &mut files)?;
+    files.sort();
+    
+    let mut batch = Batch::default();
+    // Link targets by lowercased file name and by path within the vault, both without `.md`
+    let mut targets = HashMap::new();
+    let mut ids = HashSet::new();
+    let mut provisional = 0;
+    
+    for path in &files {
+        let mut note = match read_note(path) {
+            Ok(note) => note,
+            Err(e) => {
+                batch.failures.push(format!("{}: {}", path.display(), e));
+                continue;
+            }
+        };
+        // A copied file can repeat another's id; only the first one keeps it
+        let id = match note.id.filter(|id| *id > 0 && ids.insert(*id)) {
+            Some(id) => id,
+            None => {
+                provisional -= 1;
+                provisional
+            }
+        };
+        note.id = Some(id);
+        batch.notes.push(note);
+        
+        let relative = path.strip_prefix(dir).unwrap_or(path).with_extension("");
+        targets.insert(relative.to_string_lossy().replace('\\', "/").to_lowercase(), id);
+        if let Some(stem) = path.file_stem() {
+            targets.entry(stem.to_string_lossy().to_lowercase()).or_insert(id);
+        }
+    }
+    
+    for note in &mut batch.notes {
+        note.content = convert_wikilinks(&note.content, &targets);
+    }
+    
+    Ok(batch)
+}
+
+fn collect_markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        
+        if path.is_dir() {
+            collect_markdown_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("md")) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn read_note(path: &Path) -> anyhow::Result<Note> {
+    let text = std::fs::read_to_string(path)?;
+    let (front_matter, body) = split_front_matter(&text);
+    
+    let metadata = std::fs::metadata(path)?;
+    let modified: DateTime<Utc> = metadata.modified()?.into();
+    let created: DateTime<Utc> = metadata.created().map(Into::into).unwrap_or(modified);
+    
+    let field = |keys: &[&str]| keys.iter().find_map(|key| front_matter.get(*key));
+    
+    let mut tags = field(&["tags", "tag"]).map(tag_list).unwrap_or_default();
+    for tag in inline_tags(body) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    
+    let body = body.trim();
+    // Obsidian keeps a note's title in its file name; carry it over so it's
+    // searchable, unless the note already opens with a heading. Files written
+    // by `ezn export` carry an `id` and never need one.
+    let content = match path.file_stem() {
+        Some(stem) if !body.starts_with('#') && !front_matter.contains_key("id") => {
+            format!("# {}\n\n{}", stem.to_string_lossy(), body).trim_end().to_string()
+        }
+        _ => body.to_string(),
+    };
+    
+    let created_at = field(&["created_at", "created", "date"]).and_then(parse_date).unwrap_or(created);
+    let updated_at = field(&["updated_at", "updated", "modified"]).and_then(parse_date).unwrap_or(modified);
+    
+    Ok(Note {
+        id: front_matter.get("id").and_then(Value::as_i64),
+        content,
+        priority: field(&["priority"])
+            .and_then(Value::as_str)
+            .and_then(|p| Priority::from_str(p).ok())
+            .unwrap_or(Priority::Medium),
+        created_at,
+        updated_at: updated_at.max(created_at),
+        is_archived: field(&["archived"]).and_then(Value::as_bool).unwrap_or(false),
+        tags,
+        deleted_at: None,
+        notebook: field(&["notebook"]).and_then(Value::as_str).map(str::to_string),
+    })
+}
+
+/// A front matter value. Notes only use scalars and lists for metadata, so
+/// that is all of YAML that's read; nested mappings and block scalars are
+/// skipped.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Scalar(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Scalar(s) => Some(s),
+            Value::List(_) => None,
+        }
+    }
+    
+    fn as_i64(&self) -> Option<i64> {
+        self.as_str()?.parse().ok()
+    }
+    
+    fn as_bool(&self) -> Option<bool> {
+        match self.as_str()?.to_lowercase().as_str() {
+            "true" | "yes" => Some(true),
+            "false" | "no" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Split off a leading `---` front matter block. Files without one get no fields.
+fn split_front_matter(text: &str) -> (HashMap<String, Value>, &str) {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let Some(rest) = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n")) else {
+        return (HashMap::new(), text);
+    };
+    
+    let Some(end) = rest.find("\n---") else {
+        return (HashMap::new(), text);
+    };
+    let body = rest[end + 4..].trim_start_matches(['-', '\r']);
+    let body = body.strip_prefix('\n').unwrap_or(body);
+    
+    (parse_front_matter(&rest[..end]), body)
+}
+
+/// Top-level `key: value` lines, where a value is a scalar, a `[flow, list]`,
+/// or a block list of `- item` lines under a key with no value.
+fn parse_front_matter(yaml: &str) -> HashMap<String, Value> {
+    let mut fields = HashMap::new();
+    // The key whose block list the following `- item` lines belong to
+    let mut list_key: Option<String> = None;
+    
+    for line in yaml.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        
+        if let (Some(key), Some(item)) = (&list_key, trimmed.strip_prefix('-')) {
+            if let Some(Value::List(items)) = fields.get_mut(key) {
+                items.push(unquote(item.trim()));
+            }
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        list_key = None;
+        
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let key = unquote(key.trim());
+        let value = strip_comment(value.trim());
+        
+        let value = if value.is_empty() {
+            list_key = Some(key.clone());
+            Value::List(Vec::new())
+        } else if let Some(items) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Value::List(split_flow_list(items))
+        } else {
+            Value::Scalar(unquote(value))
+        };
+        fields.insert(key, value);
+    }
+    
+    fields
+}
+
+/// Drop a ` # comment` after an unquoted value.
+fn strip_comment(value: &str) -> &str {
+    if value.starts_with(['"', '\'']) {
+        return value;
+    }
+    match value.find(" #") {
+        Some(pos) => value[..pos].trim_end(),
+        None => value,
+    }
+}
+
+/// The items of a `[a, "b, c", 'd']` list, without the brackets.
+fn split_flow_list(items: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    
+    for c in items.chars() {
+        match (quote, c) {
+            (None, ',') => parts.push(std::mem::take(&mut current)),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (Some(q), c) if c == q => {
+                quote = None;
+                current.push(c);
+            }
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+    
+    parts.iter()
+        .map(|item| unquote(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// A scalar without its quotes. Double-quoted strings use JSON's escapes,
+/// which is how `ezn export` writes them.
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        return serde_json::from_str(value).unwrap_or_else(|_| value[1..value.len() - 1].to_string());
+    }
+    if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+        return value[1..value.len() - 1].replace("''", "'");
+    }
+    value.to_string()
+}
+
+/// Tags given as a list or as a comma- or space-separated string.
+fn tag_list(value: &Value) -> Vec<String> {
+    let raw: Vec<&str> = match value {
+        Value::List(items) => items.iter().map(String::as_str).collect(),
+        Value::Scalar(s) => s.split([',', ' ']).collect(),
+    };
+    
+    raw.iter()
+        .map(|tag| tag.trim().trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// Front matter dates such as `2023-04-01`, `2023-04-01 09:30` or RFC 3339 timestamps.
+fn parse_date(value: &Value) -> Option<DateTime<Utc>> {
+    let s = value.as_str()?.trim();
+    
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(timestamp) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(timestamp.and_utc());
+        }
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+/// `#tags` in the body, outside code. Like Obsidian, a tag needs at least one
+/// non-digit and may use `/` for nesting.
+fn inline_tags(body: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut in_fence = false;
+    
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        
+        let mut in_code = false;
+        let mut prev = ' ';
+        for (i, c) in line.char_indices() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && (prev.is_whitespace() || prev == '(') {
+                let tag: String = line[i + 1..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/'))
+                    .collect();
+                if tag.chars().any(|c| !c.is_ascii_digit()) && !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            prev = c;
+        }
+    }
+    
+    tags
+}
+
+/// Turn `[[Target]]`, `[[Target|alias]]` and `[[Target#Heading]]` into
+/// `[text](ezn:ID)` when the target is in the vault. Embeds (`![[...]]`) and
+/// links to files outside the vault are left as they are.
+fn convert_wikilinks(content: &str, targets: &HashMap<String, i64>) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    
+    while let Some(start) = rest.find("[[") {
+        let Some(len) = rest[start + 2..].find("]]") else {
+            break;
+        };
+        let inner = &rest[start + 2..start + 2 + len];
+        let is_embed = rest[..start].ends_with('!');
+        
+        out.push_str(&rest[..start]);
+        rest = &rest[start + 2 + len + 2..];
+        
+        let (target, alias) = match inner.split_once('|') {
+            Some((target, alias)) => (target, Some(alias)),
+            None => (inner, None),
+        };
+        let page = target.split('#').next().unwrap_or(target).trim();
+        let page = page.strip_suffix(".md").unwrap_or(page);
+        
+        match targets.get(&page.to_lowercase()) {
+            Some(id) if !is_embed => {
+                let text = alias.unwrap_or(target).trim();
+                out.push_str(&format!("[{}]({}{})", text, LINK_SCHEME, id));
+            }
+            _ => {
+                out.push_str("[[");
+                out.push_str(inner);
+                out.push_str("]]");
+            }
+        }
+    }
+    
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn scalar(s: &str) -> Value {
+        Value::Scalar(s.to_string())
+    }
+    
+    fn list(items: &[&str]) -> Value {
+        Value::List(items.iter().map(|item| item.to_string()).collect())
+    }
+    
+    #[test]
+    fn reads_scalars_and_lists_from_front_matter() {
+        let fields = parse_front_matter(
+            "# a comment\n\
+             id: 12\n\
+             title: \"Say \\\"hi\\\"\"\n\
+             notebook: 'Bob''s notes'\n\
+             archived: yes  # done\n\
+             tags: [\"a, b\", c, 'd']\n\
+             aliases:\n\
+             \x20 - First\n\
+             \x20 - \"Second\"\n\
+             cssclasses:\n\
+             nested:\n\
+             \x20 key: value\n\
+             description: |\n\
+             \x20 more text\n"
+        );
+        
+        assert_eq!(fields["id"].as_i64(), Some(12));
+        assert_eq!(fields["title"], scalar("Say \"hi\""));
+        assert_eq!(fields["notebook"], scalar("Bob's notes"));
+        assert_eq!(fields["archived"].as_bool(), Some(true));
+        assert_eq!(fields["tags"], list(&["a, b", "c", "d"]));
+        assert_eq!(fields["aliases"], list(&["First", "Second"]));
+        assert_eq!(fields["cssclasses"], list(&[]));
+        assert_eq!(fields["nested"], list(&[]));
+        assert_eq!(fields["description"], scalar("|"));
+        assert!(!fields.contains_key("key"));
+    }
+    
+    #[test]
+    fn reads_a_vault_note() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".obsidian")).unwrap();
+        std::fs::write(dir.path().join(".obsidian").join("hidden.md"), "skipped").unwrap();
+        std::fs::write(
+            dir.path().join("Trip.md"),
+            "---\ntags: travel, plans  # for June\ncreated: 2024-05-01 08:15\npriority: high\n---\nPack for the #beach, not `#code`.\n",
+        ).unwrap();
+        
+        let batch = read_vault(dir.path()).unwrap();
+        let [note] = batch.notes.as_slice() else {
+            panic!("expected one note, got {:?}", batch.notes);
+        };
+        assert_eq!(note.content, "# Trip\n\nPack for the #beach, not `#code`.");
+        assert_eq!(note.tags, vec!["travel", "plans", "beach"]);
+        assert_eq!(note.priority, Priority::High);
+        assert_eq!(note.created_at.to_rfc3339(), "2024-05-01T08:15:00+00:00");
+        assert_eq!(note.id, Some(-1));
+    }
+}
@@ -0,0 +1,292 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::HashMap;
+
+use crate::models::{Note, Priority};
+
+use super::Batch;
+
+/// Parse an Org file, as written by `ezn export org`, into one note
+/// per top-level heading. Deeper headings stay in their parent's text.
+/// `[#A]`–`[#D]` map to urgent, high, medium and low, `DONE` headings mean
+/// archived, and the `:PROPERTIES:` drawer supplies the ID, timestamps and
+/// notebook. Tags come from `:EZN_TAGS:` when present, since those are exact;
+/// otherwise from the heading, where Org's `ARCHIVE` tag means archived.
+pub fn parse_org(text: &str) -> Batch {
+    let mut batch = Batch::default();
+    let mut entry: Option<(usize, Vec<&str>)> = None;
+    
+    for (number, line) in text.lines().enumerate() {
+        if line.starts_with("* ") || line == "*" {
+            if let Some((start, lines)) = entry.take() {
+                push_entry(&mut batch, start, &lines);
+            }
+            entry = Some((number + 1, vec![line]));
+        } else if let Some((_, lines)) = &mut entry {
+            lines.push(line);
+        }
+    }
+    if let Some((start, lines)) = entry {
+        push_entry(&mut batch, start, &lines);
+    }
+    
+    batch
+}
+
+fn push_entry(batch: &mut Batch, line_number: usize, lines: &[&str]) {
+    match parse_entry(lines) {
+        Ok(note) => batch.notes.push(note),
+        Err(e) => batch.failures.push(format!("line {}: {}", line_number, e)),
+    }
+}
+
+fn parse_entry(lines: &[&str]) -> anyhow::Result<Note> {
+    let heading = parse_heading(lines[0].trim_start_matches('*'));
+    let mut rest = &lines[1..];
+    
+    // A SCHEDULED/DEADLINE/CLOSED line may sit between the heading and the drawer
+    if rest.first().is_some_and(|line| is_planning(line)) {
+        rest = &rest[1..];
+    }
+    
+    let mut properties = HashMap::new();
+    if rest.first().is_some_and(|line| line.trim().eq_ignore_ascii_case(":PROPERTIES:")) {
+        let end = rest.iter()
+            .position(|line| line.trim().eq_ignore_ascii_case(":END:"))
+            .ok_or_else(|| anyhow::anyhow!("unterminated :PROPERTIES: drawer"))?;
+        for line in &rest[1..end] {
+            if let Some((key, value)) = line.trim().strip_prefix(':').and_then(|l| l.split_once(':')) {
+                properties.insert(key.to_uppercase(), value.trim().to_string());
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    
+    let mut body: Vec<&str> = rest.iter()
+        .map(|line| line.strip_prefix(',').filter(|l| l.trim_start_matches(',').starts_with('*')).unwrap_or(line))
+        .collect();
+    while body.last().is_some_and(|line| line.trim().is_empty()) {
+        body.pop();
+    }
+    
+    let (title, tags, archive_tag) = match properties.get("EZN_TAGS") {
+        Some(exact) => {
+            let tags: Vec<String> = serde_json::from_str(exact)
+                .map_err(|e| anyhow::anyhow!("invalid :EZN_TAGS: property: {}", e))?;
+            // Export only adds heading tags to notes that have some, so without
+            // any, a tag-like ending is part of the note's first line
+            let title = if tags.is_empty() { heading.full_title } else { heading.title };
+            (title, tags, false)
+        }
+        None => {
+            let archive_tag = heading.tags.contains(&"ARCHIVE");
+            let tags = heading.tags.iter().filter(|tag| **tag != "ARCHIVE").map(|tag| tag.to_string()).collect();
+            (heading.title, tags, archive_tag)
+        }
+    };
+    
+    let content = if body.is_empty() {
+        title.to_string()
+    } else {
+        format!("{}\n{}", title, body.join("\n"))
+    };
+    if content.trim().is_empty() {
+        return Err(anyhow::anyhow!("heading has no text"));
+    }
+    
+    let timestamp = |key: &str| -> anyhow::Result<Option<DateTime<Utc>>> {
+        properties.get(key)
+            .map(|value| parse_timestamp(value).ok_or_else(|| anyhow::anyhow!("invalid :{}: timestamp '{}'", key, value)))
+            .transpose()
+    };
+    let updated = timestamp("UPDATED")?;
+    let created_at = timestamp("CREATED")?.or(updated).unwrap_or_else(Utc::now);
+    
+    Ok(Note {
+        id: properties.get("ID").and_then(|id| id.parse().ok()),
+        content,
+        priority: heading.priority.unwrap_or(Priority::Medium),
+        created_at,
+        updated_at: updated.unwrap_or(created_at).max(created_at),
+        is_archived: heading.done || archive_tag,
+        tags,
+        deleted_at: None,
+        notebook: properties.get("NOTEBOOK").filter(|name| !name.is_empty()).cloned(),
+    })
+}
+
+struct Heading<'a> {
+    done: bool,
+    priority: Option<Priority>,
+    title: &'a str,
+    /// The title with any trailing tags left on
+    full_title: &'a str,
+    tags: Vec<&'a str>,
+}
+
+/// Split `TODO [#A] Title   :tag1:tag2:` into its parts.
+fn parse_heading(text: &str) -> Heading<'_> {
+    let mut text = text.trim();
+    
+    let mut done = false;
+    for (keyword, is_done) in [("TODO", false), ("DONE", true)] {
+        if let Some(rest) = text.strip_prefix(keyword).filter(|r| r.is_empty() || r.starts_with(' ')) {
+            done = is_done;
+            text = rest.trim_start();
+            break;
+        }
+    }
+    
+    let mut priority = None;
+    if let Some(rest) = text.strip_prefix("[#") {
+        let mut chars = rest.chars();
+        if let (Some(letter), Some(']')) = (chars.next(), chars.next()) {
+            priority = Priority::from_letter(letter);
+            if priority.is_some() {
+                text = rest[2..].strip_prefix(' ').unwrap_or(&rest[2..]);
+            }
+        }
+    }
+    
+    let (title, tags) = split_tags(text);
+    Heading { done, priority, title, full_title: text.trim_end(), tags }
+}
+
+/// Split a trailing Org tag group such as `:tag1:tag2:` off a heading's text.
+pub fn split_tags(text: &str) -> (&str, Vec<&str>) {
+    let trimmed = text.trim_end();
+    let start = trimmed.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let candidate = &trimmed[start..];
+    if candidate.len() > 2 && candidate.starts_with(':') && candidate.ends_with(':')
+        && !candidate.contains("::")
+        && candidate.chars().all(|c| c == ':' || c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%'))
+    {
+        (trimmed[..start].trim_end(), candidate.trim_matches(':').split(':').collect())
+    } else {
+        (trimmed, Vec::new())
+    }
+}
+
+fn is_planning(line: &str) -> bool {
+    let line = line.trim_start();
+    ["SCHEDULED:", "DEADLINE:", "CLOSED:"].iter().any(|keyword| line.starts_with(keyword))
+}
+
+/// RFC 3339 as written by ezn, or an Org timestamp such as `[2024-03-01 Fri 09:30]`.
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.with_timezone(&Utc));
+    }
+    
+    let inner = value.trim_matches(|c| matches!(c, '[' | ']' | '<' | '>'));
+    let mut parts = inner.split_whitespace();
+    let date = parts.next()?;
+    let time = parts.find(|part| part.contains(':')).unwrap_or("00:00");
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").ok().map(|t| t.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::org::write_notes;
+    
+    fn note(content: &str, tags: &[&str]) -> Note {
+        let created_at = "2026-03-01T09:30:00Z".parse().unwrap();
+        Note {
+            id: Some(1),
+            content: content.to_string(),
+            priority: Priority::High,
+            created_at,
+            updated_at: created_at,
+            is_archived: false,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            deleted_at: None,
+            notebook: Some("work".to_string()),
+        }
+    }
+    
+    fn round_trip(note: &Note) -> Note {
+        let mut out = Vec::new();
+        write_notes(std::slice::from_ref(note), &mut out).unwrap();
+        let batch = parse_org(&String::from_utf8(out).unwrap());
+        assert!(batch.failures.is_empty(), "{:?}", batch.failures);
+        assert_eq!(batch.notes.len(), 1);
+        batch.notes.into_iter().next().unwrap()
+    }
+    
+    #[test]
+    fn round_trips_tags_exactly() {
+        for (content, tags) in [
+            ("Release checklist :ready:", &[][..]),
+            ("Release checklist :ready:", &["ship"][..]),
+            ("Old plans", &["ARCHIVE"][..]),
+            ("Odd tags", &["a-b", "c d"][..]),
+            ("Plain\n* not a heading\nmore", &["work", "@home"][..]),
+            ("Commas\n,* one\n,,* two\n,,,*** three\n,, none", &[][..]),
+        ] {
+            let original = note(content, tags);
+            let imported = round_trip(&original);
+            assert_eq!(imported.content, original.content);
+            assert_eq!(imported.tags, original.tags);
+            assert!(!imported.is_archived);
+            assert_eq!(imported.priority, Priority::High);
+            assert_eq!(imported.created_at, original.created_at);
+            assert_eq!(imported.notebook, original.notebook);
+        }
+    }
+    
+    #[test]
+    fn round_trips_priority_and_archived_state() {
+        for priority in [Priority::Low, Priority::Medium, Priority::High, Priority::Urgent] {
+            for archived in [false, true] {
+                let mut original = note("Task", &["work"]);
+                original.priority = priority.clone();
+                original.is_archived = archived;
+                original.updated_at = "2026-03-02T10:00:00Z".parse().unwrap();
+                
+                let imported = round_trip(&original);
+                assert_eq!(imported.priority, original.priority);
+                assert_eq!(imported.is_archived, archived);
+                assert_eq!(imported.updated_at, original.updated_at);
+                assert_eq!(imported.tags, original.tags);
+            }
+        }
+    }
+    
+    #[test]
+    fn reads_org_files_from_elsewhere() {
+        let batch = parse_org(
+            "#+TITLE: Inbox\n\
+             * DONE [#A] Call the bank   :errands:ARCHIVE:\n\
+             CLOSED: [2024-03-02 Sat 10:00]\n\
+             :PROPERTIES:\n\
+             :CREATED:  [2024-03-01 Fri 09:30]\n\
+             :END:\n\
+             ** Sub-heading\n\
+             Details\n\
+             \n\
+             * Second\n",
+        );
+        assert!(batch.failures.is_empty());
+        assert_eq!(batch.notes.len(), 2);
+        
+        let first = &batch.notes[0];
+        assert_eq!(first.content, "Call the bank\n** Sub-heading\nDetails");
+        assert_eq!(first.tags, vec!["errands"]);
+        assert!(first.is_archived);
+        assert_eq!(first.priority, Priority::Urgent);
+        assert_eq!(first.created_at.to_rfc3339(), "2024-03-01T09:30:00+00:00");
+        
+        assert_eq!(batch.notes[1].content, "Second");
+        assert_eq!(batch.notes[1].priority, Priority::Medium);
+    }
+    
+    #[test]
+    fn reports_bad_entries_with_their_line() {
+        let batch = parse_org("* One\n:PROPERTIES:\n:ID: 1\n* Two\n:PROPERTIES:\n:CREATED: soon\n:END:\n");
+        assert!(batch.notes.is_empty());
+        assert_eq!(batch.failures, vec![
+            "line 1: unterminated :PROPERTIES: drawer",
+            "line 4: invalid :CREATED: timestamp 'soon'",
+        ]);
+    }
+}
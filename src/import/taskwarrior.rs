@@ -0,0 +1,150 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+
+use crate::models::{Note, Priority};
+
+use super::Batch;
+
+/// One task as written by `task export`.
+#[derive(Deserialize)]
+struct Task {
+    description: String,
+    #[serde(default)]
+    status: String,
+    priority: Option<String>,
+    project: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+    entry: Option<String>,
+    modified: Option<String>,
+    end: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    description: String,
+}
+
+/// Parse `task export` output: a JSON array, or one task object per line as
+/// older Taskwarrior versions write it. Completed tasks are archived and
+/// deleted tasks go to the trash.
+pub fn parse_export(text: &str) -> anyhow::Result<Batch> {
+    let values: Vec<serde_json::Value> = match serde_json::from_str(text) {
+        Ok(values) => values,
+        Err(array_error) => text.lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|line| !line.is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()
+            .map_err(|_| anyhow::anyhow!("Not a Taskwarrior export: {}", array_error))?,
+    };
+    
+    let mut batch = Batch::default();
+    for (index, value) in values.into_iter().enumerate() {
+        match serde_json::from_value::<Task>(value) {
+            Ok(task) => batch.notes.push(to_note(task)),
+            Err(e) => batch.failures.push(format!("task {}: {}", index + 1, e)),
+        }
+    }
+    
+    Ok(batch)
+}
+
+fn to_note(task: Task) -> Note {
+    let end = task.end.as_deref().and_then(parse_timestamp);
+    let modified = task.modified.as_deref().and_then(parse_timestamp).or(end);
+    let created_at = task.entry.as_deref()
+        .and_then(parse_timestamp)
+        .or(modified)
+        .unwrap_or_else(Utc::now);
+    let updated_at = modified.unwrap_or(created_at).max(created_at);
+    
+    let mut content = task.description.trim().to_string();
+    let annotations: Vec<String> = task.annotations.iter()
+        .map(|a| format!("- {}", a.description.trim()))
+        .collect();
+    if !annotations.is_empty() {
+        content = format!("{}\n\n{}", content, annotations.join("\n"));
+    }
+    
+    let mut tags = Vec::new();
+    for tag in task.project.into_iter().chain(task.tags) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    
+    let deleted_at = (task.status == "deleted")
+        .then(|| end.unwrap_or(updated_at));
+    
+    Note {
+        id: None,
+        content,
+        priority: match task.priority.as_deref() {
+            Some("H") => Priority::High,
+            Some("L") => Priority::Low,
+            _ => Priority::Medium,
+        },
+        created_at,
+        updated_at,
+        is_archived: task.status == "completed",
+        tags,
+        deleted_at,
+        notebook: None,
+    }
+}
+
+/// Taskwarrior timestamps look like `20240301T093000Z`.
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(s.trim(), "%Y%m%dT%H%M%SZ").ok().map(|t| t.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const TASKS: &str = r#"[
+        {"id":1,"description":"Write report","status":"pending","priority":"H","project":"work","tags":["writing","work"],
+         "entry":"20240301T093000Z","modified":"20240302T100000Z",
+         "annotations":[{"entry":"20240301T094000Z","description":"draft in docs/ "},{"entry":"20240302T094000Z","description":"ask Sam"}]},
+        {"id":0,"description":"File taxes","status":"completed","priority":"L","entry":"20240101T080000Z","end":"20240410T170000Z"},
+        {"id":0,"description":"Old idea","status":"deleted","entry":"20240105T080000Z","end":"20240106T080000Z","modified":"20240107T080000Z"},
+        {"id":2,"status":"pending"}
+    ]"#;
+    
+    #[test]
+    fn reads_a_task_export() {
+        let batch = parse_export(TASKS).unwrap();
+        
+        assert_eq!(batch.failures, vec!["task 4: missing field `description`"]);
+        let [report, taxes, idea] = batch.notes.as_slice() else {
+            panic!("expected three tasks, got {:?}", batch.notes);
+        };
+        
+        assert_eq!(report.content, "Write report\n\n- draft in docs/\n- ask Sam");
+        assert_eq!(report.priority, Priority::High);
+        assert_eq!(report.tags, vec!["work", "writing"]);
+        assert_eq!(report.created_at.to_rfc3339(), "2024-03-01T09:30:00+00:00");
+        assert_eq!(report.updated_at.to_rfc3339(), "2024-03-02T10:00:00+00:00");
+        assert!(!report.is_archived && report.deleted_at.is_none());
+        
+        assert_eq!(taxes.priority, Priority::Low);
+        assert!(taxes.is_archived);
+        assert_eq!(taxes.updated_at.to_rfc3339(), "2024-04-10T17:00:00+00:00");
+        
+        assert_eq!(idea.priority, Priority::Medium);
+        assert_eq!(idea.deleted_at.map(|at| at.to_rfc3339()).as_deref(), Some("2024-01-06T08:00:00+00:00"));
+    }
+    
+    #[test]
+    fn reads_one_task_per_line() {
+        let text = "{\"description\":\"a\",\"status\":\"pending\"},\n{\"description\":\"b\",\"status\":\"completed\"}\n";
+        let batch = parse_export(text).unwrap();
+        assert_eq!(batch.notes.iter().map(|n| n.content.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert!(batch.notes[1].is_archived);
+        
+        assert!(parse_export("not json").unwrap_err().to_string().starts_with("Not a Taskwarrior export"));
+    }
+}
@@ -0,0 +1,170 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::models::{Note, Priority};
+
+use super::Batch;
+
+/// Parse a todo.txt file, one task per line. `(A)`–`(D)` map to urgent, high,
+/// medium and low (later letters to low), `+project` and `@context` become
+/// tags (`project` and `@context`), and completed `x` tasks are archived.
+pub fn parse_todotxt(text: &str) -> Batch {
+    let mut batch = Batch::default();
+    
+    for (number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_task(line) {
+            Some(note) => batch.notes.push(note),
+            None => batch.failures.push(format!("line {}: no task text", number + 1)),
+        }
+    }
+    
+    batch
+}
+
+fn parse_task(line: &str) -> Option<Note> {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    
+    let completed = words.first() == Some(&"x");
+    if completed {
+        words.remove(0);
+    }
+    
+    let mut priority = None;
+    if let Some(letter) = words.first().and_then(|w| parse_priority(w)) {
+        priority = Some(letter);
+        words.remove(0);
+    }
+    
+    // A completed task lists its completion date before its creation date
+    let mut dates = Vec::new();
+    while dates.len() < if completed { 2 } else { 1 } {
+        match words.first().and_then(|w| parse_date(w)) {
+            Some(date) => {
+                dates.push(date);
+                words.remove(0);
+            }
+            None => break,
+        }
+    }
+    let (completed_at, created_at) = match (completed, dates.as_slice()) {
+        (true, [done, created]) => (Some(*done), Some(*created)),
+        (true, [done]) => (Some(*done), None),
+        (false, [created]) => (None, Some(*created)),
+        _ => (None, None),
+    };
+    
+    let mut tags = Vec::new();
+    let mut text = Vec::new();
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            push_unique(&mut tags, project.to_string());
+        } else if word.len() > 1 && word.starts_with('@') {
+            push_unique(&mut tags, word.to_string());
+        } else if let Some(letter) = word.strip_prefix("pri:").and_then(single_letter) {
+            // Completed tasks keep their priority as a `pri:` tag
+            priority = priority.or(Some(letter));
+        } else {
+            text.push(word);
+        }
+    }
+    
+    if text.is_empty() {
+        return None;
+    }
+    
+    let created_at = created_at.or(completed_at).unwrap_or_else(Utc::now);
+    Some(Note {
+        id: None,
+        content: text.join(" "),
+        priority: priority.unwrap_or(Priority::Medium),
+        created_at,
+        updated_at: completed_at.unwrap_or(created_at).max(created_at),
+        is_archived: completed,
+        tags,
+        deleted_at: None,
+        notebook: None,
+    })
+}
+
+/// `(A)` through `(Z)`.
+fn parse_priority(word: &str) -> Option<Priority> {
+    single_letter(word.strip_prefix('(')?.strip_suffix(')')?)
+}
+
+fn single_letter(text: &str) -> Option<Priority> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(letter), None) => Priority::from_letter(letter),
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+}
+
+fn push_unique(tags: &mut Vec<String>, tag: String) {
+    if !tags.contains(&tag) {
+        tags.push(tag);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::todotxt::write_notes;
+    
+    #[test]
+    fn reads_priorities_dates_and_tags() {
+        let batch = parse_todotxt(
+            "(A) 2026-03-01 Call mom +family @phone due:2026-03-05\n\
+             \n\
+             x 2026-03-04 2026-03-01 Pay rent +home +home pri:B\n\
+             x Water plants\n\
+             (E) Someday (a) maybe\n\
+             +errands @town\n"
+        );
+        
+        assert_eq!(batch.failures, vec!["line 6: no task text"]);
+        let [call, rent, water, someday] = batch.notes.as_slice() else {
+            panic!("expected four tasks, got {:?}", batch.notes);
+        };
+        
+        assert_eq!(call.content, "Call mom due:2026-03-05");
+        assert_eq!(call.priority, Priority::Urgent);
+        assert_eq!(call.tags, vec!["family", "@phone"]);
+        assert_eq!(call.created_at.to_rfc3339(), "2026-03-01T00:00:00+00:00");
+        assert!(!call.is_archived);
+        
+        assert_eq!(rent.content, "Pay rent");
+        assert_eq!(rent.priority, Priority::High);
+        assert_eq!(rent.tags, vec!["home"]);
+        assert_eq!(rent.created_at.to_rfc3339(), "2026-03-01T00:00:00+00:00");
+        assert_eq!(rent.updated_at.to_rfc3339(), "2026-03-04T00:00:00+00:00");
+        assert!(rent.is_archived);
+        
+        assert_eq!(water.content, "Water plants");
+        assert_eq!(water.priority, Priority::Medium);
+        assert!(water.is_archived);
+        
+        assert_eq!(someday.content, "Someday (a) maybe");
+        assert_eq!(someday.priority, Priority::Low);
+    }
+    
+    #[test]
+    fn round_trips_through_export() {
+        let text = "(A) 2026-03-01 Call mom +family @phone\n\
+                    (D) 2026-02-01 Read a book\n\
+                    2026-02-02 Plain task +home\n\
+                    x 2026-03-04 2026-03-01 Pay rent +home pri:B\n\
+                    x 2026-03-05 2026-03-02 Water plants\n";
+        
+        let batch = parse_todotxt(text);
+        let mut out = Vec::new();
+        write_notes(&batch.notes, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), text);
+    }
+}
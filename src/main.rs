@@ -2,11 +2,17 @@ use clap::Parser;
 use colored::*;
 
 mod cli;
+mod config;
 mod db;
+mod export;
+mod import;
 mod models;
+mod query;
 mod services;
+mod utils;
 
-use cli::Cli;
+use cli::{Cli, OutputFormat};
+use config::{Config, Theme};
 use db::Database;
 use services::NoteService;
 
@@ -14,16 +20,38 @@ fn main() -> anyhow::Result<()> {
     // Parse CLI arguments
     let cli = Cli::parse();
     
-    // Initialize database
-    let db = Database::new()?;
-    let note_service = NoteService::new(db);
-    
     // Execute command
-    match cli.execute(&note_service) {
+    match run(&cli) {
         Ok(_) => Ok(()),
         Err(e) => {
-            eprintln!("{} {}", "Error:".red().bold(), e);
+            if cli.output() == OutputFormat::Text {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+            } else {
+                eprintln!("{}", serde_json::json!({ "error": e.to_string() }));
+            }
             std::process::exit(1);
         }
     }
 }
+
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    let config = Config::load();
+    if let Ok(Config { theme: Some(Theme::Mono), .. }) = &config {
+        colored::control::set_override(false);
+    }
+    
+    // `ezn config` loads what it needs itself, so it still works when a config file is broken
+    if !cli.needs_database() {
+        return cli.execute_config();
+    }
+    let config = config?;
+    
+    // Initialize database (`ezn db migrate` applies migrations itself)
+    let db = Database::open(&cli.db_path(&config)?)?;
+    if !cli.manages_migrations() {
+        db.migrate()?;
+    }
+    let note_service = NoteService::new(db);
+    
+    cli.execute(&note_service, &config)
+}
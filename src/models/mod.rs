@@ -1,6 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+/// Scheme for links between notes, as in `[Deploy checklist](ezn:12)`.
+pub const LINK_SCHEME: &str = "ezn:";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
     pub id: Option<i64>,
@@ -8,11 +11,27 @@ pub struct Note {
     pub priority: Priority,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
     pub is_archived: bool,
+    #[serde(default)]
     pub tags: Vec<String>,
+    /// Set while the note sits in the trash
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub notebook: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notebook {
+    pub id: i64,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub note_count: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum Priority {
     Low,
     Medium,
@@ -39,9 +58,152 @@ impl Priority {
             Priority::Urgent => "urgent",
         }
     }
+    
+    /// The letter todo.txt and Org use for this priority, `A` (urgent) to `D` (low).
+    pub fn letter(&self) -> char {
+        match self {
+            Priority::Urgent => 'A',
+            Priority::High => 'B',
+            Priority::Medium => 'C',
+            Priority::Low => 'D',
+        }
+    }
+    
+    /// The reverse of `letter`. Letters after `D` mean low, since both formats allow up to `Z`.
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'A' => Some(Priority::Urgent),
+            'B' => Some(Priority::High),
+            'C' => Some(Priority::Medium),
+            'D'..='Z' => Some(Priority::Low),
+            _ => None,
+        }
+    }
+}
+
+/// A snapshot of a note's content, priority and tags at one point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revision {
+    pub note_id: i64,
+    pub revision: i64,
+    pub content: String,
+    pub priority: Priority,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A journaled command that `ezn undo` / `ezn redo` can invert or replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub id: i64,
+    pub command: String,
+    pub note_ids: Vec<i64>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What `import_notes` did with each incoming note.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImportSummary {
+    /// `(original ID, new ID)` for every note created
+    pub imported: Vec<(Option<i64>, i64)>,
+    /// Notes skipped because one with the same content and `created_at` exists
+    pub duplicates: usize,
+    /// Notes skipped because they had no content
+    pub skipped_empty: usize,
+    /// Entries the source format couldn't be converted from, with the reason
+    pub failures: Vec<String>,
 }
 
+/// How `ezn search` orders its results.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SearchOrder {
+    /// Most recently created first
+    Newest,
+    /// Best full-text matches first
+    Relevance,
+}
+
+/// How `ezn search` reads its query text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchMode {
+    /// The query language: words, phrases, fields and operators
+    Query,
+    /// Every word as plain text
+    Literal,
+    /// An FTS5 expression, passed to SQLite as written
+    RawFts,
+}
+
+/// Marks the start of a matched term in `SearchResult::snippet`.
+pub const MATCH_START: char = '\u{2}';
+/// Marks the end of a matched term in `SearchResult::snippet`.
+pub const MATCH_END: char = '\u{3}';
+
+/// A note found by `ezn search`.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub note: Note,
+    /// The text around the matches, when the query searched for words
+    pub snippet: Option<String>,
+}
+
+/// The notes found by a search, and why the query was searched for as
+/// literal words instead, if it was.
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    pub results: Vec<SearchResult>,
+    pub fallback: Option<String>,
+}
+
+/// Which notes a listing should include with respect to `is_archived`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFilter {
+    Active,
+    Archived,
+    All,
+}
+
+impl ArchiveFilter {
+    /// SQL condition on the archive flag for a `notes` table aliased as `alias`.
+    pub fn sql_condition(&self, alias: &str) -> String {
+        match self {
+            ArchiveFilter::Active => format!("{}.is_archived = 0", alias),
+            ArchiveFilter::Archived => format!("{}.is_archived = 1", alias),
+            ArchiveFilter::All => "1 = 1".to_string(),
+        }
+    }
+}
+
+/// Criteria for listing notes. Trashed notes are never included.
 #[derive(Debug, Clone)]
+pub struct NoteFilter {
+    pub archived: ArchiveFilter,
+    /// Restrict to one notebook; `None` means every notebook
+    pub notebook_id: Option<i64>,
+    pub tag: Option<String>,
+    pub priority: Option<Priority>,
+    /// Only notes created at or after this instant
+    pub since: Option<DateTime<Utc>>,
+    /// Only notes created before this instant
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl Default for NoteFilter {
+    fn default() -> Self {
+        NoteFilter {
+            archived: ArchiveFilter::Active,
+            notebook_id: None,
+            tag: None,
+            priority: None,
+            since: None,
+            until: None,
+            limit: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Stats {
     pub total: usize,
     pub today: usize,
@@ -51,4 +213,5 @@ pub struct Stats {
     pub high: usize,
     pub medium: usize,
     pub low: usize,
+    pub archived: usize,
 }
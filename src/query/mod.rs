@@ -0,0 +1,579 @@
+//! The `ezn search` query language: free text, quoted phrases and field
+//! filters such as `tag:work` or `created:<7d`, combined with `AND`, `OR`,
+//! `NOT` (or a leading `-`) and parentheses. Terms next to each other must
+//! all match.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+use crate::models::Priority;
+use crate::utils::{parse_duration, start_of_today};
+
+mod sql;
+
+/// Field names accepted before a `:`.
+pub const FIELDS: &[&str] = &["tag", "priority", "created", "updated", "archived", "notebook"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    /// A word, matched with full-text search. `prefix` for `word*`.
+    Word { text: String, prefix: bool },
+    /// A quoted phrase, matched with full-text search
+    Phrase(String),
+    /// Text matched anywhere in a note, even inside a word, for `--substring`
+    Substring(String),
+    Tag(String),
+    Priority(Comparison, Priority),
+    Created(TimeRange),
+    Updated(TimeRange),
+    Archived(bool),
+    Notebook(String),
+    /// An FTS5 expression used as written, for `ezn search --raw-fts`
+    Raw(String),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Bounds on a timestamp; each bound is `(instant, inclusive)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeRange {
+    pub after: Option<(DateTime<Utc>, bool)>,
+    pub before: Option<(DateTime<Utc>, bool)>,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(anyhow::anyhow!("Empty search query"));
+        }
+        
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(query),
+            Some(Token::RParen) => Err(anyhow::anyhow!("Unbalanced ')' in search query")),
+            Some(token) => Err(anyhow::anyhow!("Unexpected {} in search query", token.describe())),
+        }
+    }
+    
+    /// Every whitespace-separated word in `input` as plain text to look for,
+    /// with no fields, operators or quoting. Used for `--literal`, and when a
+    /// query doesn't parse. Stray punctuation such as a lone `+` is dropped
+    /// when there are real words, since word search can't find it anyway.
+    pub fn literal(input: &str) -> anyhow::Result<Self> {
+        let has_text = |word: &&str| word.chars().any(char::is_alphanumeric);
+        let all_punctuation = !input.split_whitespace().any(|word| has_text(&word));
+        let mut words: Vec<Query> = input.split_whitespace()
+            .filter(|word| all_punctuation || has_text(word))
+            .map(|word| Query::Word { text: word.to_string(), prefix: false })
+            .collect();
+        match words.len() {
+            0 => Err(anyhow::anyhow!("Empty search query")),
+            1 => Ok(words.remove(0)),
+            _ => Ok(Query::And(words)),
+        }
+    }
+    
+    /// The query with its words and phrases matched as substrings instead,
+    /// so `auth` finds `OAuthProvider`.
+    pub fn into_substring(self) -> Self {
+        match self {
+            Query::Word { text, .. } | Query::Phrase(text) => Query::Substring(text),
+            Query::Not(inner) => Query::Not(Box::new(inner.into_substring())),
+            Query::And(parts) => Query::And(parts.into_iter().map(Query::into_substring).collect()),
+            Query::Or(parts) => Query::Or(parts.into_iter().map(Query::into_substring).collect()),
+            query => query,
+        }
+    }
+    
+    /// The plain words the query looks for outside any `NOT`, without phrases
+    /// or `word*` prefixes.
+    pub fn words(&self) -> Vec<&str> {
+        match self {
+            Query::Word { text, prefix: false } => vec![text.as_str()],
+            Query::And(parts) | Query::Or(parts) => parts.iter().flat_map(Query::words).collect(),
+            _ => Vec::new(),
+        }
+    }
+    
+    /// The query with each plain word also matching the alternatives `f`
+    /// gives for it, as in `(meeting OR meetnig)`.
+    pub fn expand_words(self, f: &mut impl FnMut(&str) -> anyhow::Result<Vec<String>>) -> anyhow::Result<Self> {
+        Ok(match self {
+            Query::Word { text, prefix: false } => {
+                let alternatives = f(&text)?;
+                let mut parts = vec![Query::Word { text, prefix: false }];
+                parts.extend(alternatives.into_iter().map(|text| Query::Word { text, prefix: false }));
+                if parts.len() == 1 { parts.remove(0) } else { Query::Or(parts) }
+            }
+            Query::Not(inner) => Query::Not(Box::new(inner.expand_words(f)?)),
+            Query::And(parts) => Query::And(parts.into_iter().map(|part| part.expand_words(f)).collect::<anyhow::Result<_>>()?),
+            Query::Or(parts) => Query::Or(parts.into_iter().map(|part| part.expand_words(f)).collect::<anyhow::Result<_>>()?),
+            query => query,
+        })
+    }
+    
+    /// Whether any word or phrase looks like code, such as `getUserById`,
+    /// `foo-bar` or `src/auth/mod.rs`, which word search would split apart.
+    pub fn has_code_terms(&self) -> bool {
+        match self {
+            Query::Word { text, .. } | Query::Phrase(text) => looks_like_code(text),
+            Query::Not(inner) => inner.has_code_terms(),
+            Query::And(parts) | Query::Or(parts) => parts.iter().any(Query::has_code_terms),
+            _ => false,
+        }
+    }
+    
+    /// Whether the query filters on `field` anywhere, e.g. so an explicit
+    /// `archived:yes` isn't cancelled out by the default of hiding archived notes.
+    pub fn mentions(&self, field: &str) -> bool {
+        match self {
+            Query::Archived(_) => field == "archived",
+            Query::Notebook(_) => field == "notebook",
+            Query::Not(inner) => inner.mentions(field),
+            Query::And(parts) | Query::Or(parts) => parts.iter().any(|part| part.mentions(field)),
+            _ => false,
+        }
+    }
+}
+
+/// Punctuation inside a word (not just a trailing full stop or comma), or
+/// mixed case with a capital after the first letter, as in `getUser` or `OAuth`.
+fn looks_like_code(text: &str) -> bool {
+    let text = text.trim_matches(|c: char| matches!(c, '.' | ',' | ';' | '!' | '?' | '\'' | '"'));
+    let mixed_case = text.chars().any(char::is_lowercase) && text.chars().skip(1).any(char::is_uppercase);
+    mixed_case || text.chars().any(|c| c.is_ascii_punctuation() && c != '\'')
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// A bare word or `field:value`, with any quotes around the value removed
+    Word(String),
+    /// A `"quoted phrase"` on its own
+    Phrase(String),
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match self {
+            Token::LParen => "'('".to_string(),
+            Token::RParen => "')'".to_string(),
+            Token::And => "AND".to_string(),
+            Token::Or => "OR".to_string(),
+            Token::Not => "NOT".to_string(),
+            Token::Word(word) => format!("'{}'", word),
+            Token::Phrase(phrase) => format!("\"{}\"", phrase),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '"' => {
+                chars.next();
+                tokens.push(Token::Phrase(read_quoted(&mut chars)?));
+            }
+            '-' => {
+                chars.next();
+                match chars.peek() {
+                    Some(next) if !next.is_whitespace() && *next != ')' => tokens.push(Token::Not),
+                    _ => tokens.push(Token::Word("-".to_string())),
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    // `field:"quoted value"`
+                    if c == '"' && word.ends_with(':') {
+                        word.push_str(&read_quoted(&mut chars)?);
+                        break;
+                    }
+                    word.push(c);
+                }
+                tokens.push(match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    
+    Ok(tokens)
+}
+
+/// Read up to the closing `"`, the opening one having been consumed.
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> anyhow::Result<String> {
+    let mut text = String::new();
+    for c in chars.by_ref() {
+        if c == '"' {
+            return Ok(text);
+        }
+        text.push(c);
+    }
+    Err(anyhow::anyhow!("Unterminated quote at '\"{}' in search query", text))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+    
+    fn or(&mut self) -> anyhow::Result<Query> {
+        let mut parts = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            parts.push(self.and()?);
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Query::Or(parts) })
+    }
+    
+    fn and(&mut self) -> anyhow::Result<Query> {
+        let mut parts = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                None | Some(Token::Or) | Some(Token::RParen) => break,
+                Some(Token::And) => {
+                    self.pos += 1;
+                    parts.push(self.unary()?);
+                }
+                // Adjacent terms are implicitly ANDed
+                Some(_) => parts.push(self.unary()?),
+            }
+        }
+        Ok(if parts.len() == 1 { parts.remove(0) } else { Query::And(parts) })
+    }
+    
+    fn unary(&mut self) -> anyhow::Result<Query> {
+        if self.peek() == Some(&Token::Not) {
+            self.pos += 1;
+            return Ok(Query::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+    
+    fn primary(&mut self) -> anyhow::Result<Query> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        
+        match token {
+            Some(Token::LParen) => {
+                let query = self.or()?;
+                if self.peek() != Some(&Token::RParen) {
+                    return Err(anyhow::anyhow!("Missing ')' in search query"));
+                }
+                self.pos += 1;
+                Ok(query)
+            }
+            Some(Token::Phrase(phrase)) => Ok(Query::Phrase(phrase)),
+            Some(Token::Word(word)) => parse_term(&word),
+            Some(token) => Err(anyhow::anyhow!("Expected a search term but found {}", token.describe())),
+            None => Err(anyhow::anyhow!("Search query ends where a search term was expected")),
+        }
+    }
+}
+
+fn parse_term(word: &str) -> anyhow::Result<Query> {
+    let Some((field, value)) = word.split_once(':').filter(|(field, _)| {
+        !field.is_empty() && field.chars().all(|c| c.is_ascii_alphabetic())
+    }) else {
+        return Ok(match word.strip_suffix('*') {
+            Some(stem) if !stem.is_empty() => Query::Word { text: stem.to_string(), prefix: true },
+            _ => Query::Word { text: word.to_string(), prefix: false },
+        });
+    };
+    
+    let field = field.to_lowercase();
+    if !FIELDS.contains(&field.as_str()) {
+        return Err(anyhow::anyhow!(
+            "Unknown search field '{}'. Use {}, or put the term in quotes to search for it",
+            field,
+            FIELDS.join(", ")
+        ));
+    }
+    if value.is_empty() {
+        return Err(anyhow::anyhow!("Missing value after '{}:'", field));
+    }
+    
+    let (comparison, value) = split_comparison(value);
+    let plain = |comparison: Comparison| -> anyhow::Result<()> {
+        if comparison != Comparison::Eq {
+            return Err(anyhow::anyhow!("'{}:' doesn't support <, >, <= or >=", field));
+        }
+        Ok(())
+    };
+    
+    match field.as_str() {
+        "tag" => {
+            plain(comparison)?;
+            Ok(Query::Tag(value.trim_start_matches('#').to_string()))
+        }
+        "priority" => Ok(Query::Priority(comparison, Priority::from_str(value)?)),
+        "created" => Ok(Query::Created(time_range(comparison, value)?)),
+        "updated" => Ok(Query::Updated(time_range(comparison, value)?)),
+        "archived" => {
+            plain(comparison)?;
+            match value.to_lowercase().as_str() {
+                "yes" | "true" | "1" => Ok(Query::Archived(true)),
+                "no" | "false" | "0" => Ok(Query::Archived(false)),
+                _ => Err(anyhow::anyhow!("Invalid archived value: {}. Use yes or no", value)),
+            }
+        }
+        "notebook" => {
+            plain(comparison)?;
+            Ok(Query::Notebook(value.to_string()))
+        }
+        _ => unreachable!("field names are checked against FIELDS"),
+    }
+}
+
+fn split_comparison(value: &str) -> (Comparison, &str) {
+    for (prefix, comparison) in [
+        (">=", Comparison::Ge),
+        ("<=", Comparison::Le),
+        (">", Comparison::Gt),
+        ("<", Comparison::Lt),
+        ("=", Comparison::Eq),
+    ] {
+        if let Some(rest) = value.strip_prefix(prefix) {
+            return (comparison, rest);
+        }
+    }
+    (Comparison::Eq, value)
+}
+
+/// Turn `created:`/`updated:` values into bounds. Dates compare whole days
+/// (`>2026-01-01` starts the day after), timestamps compare exactly, and ages
+/// compare how long ago: `<7d` means within the last week, as does plain `7d`.
+fn time_range(comparison: Comparison, value: &str) -> anyhow::Result<TimeRange> {
+    let value = value.trim();
+    let range = |after: Option<(DateTime<Utc>, bool)>, before: Option<(DateTime<Utc>, bool)>| {
+        Ok(TimeRange { after, before })
+    };
+    
+    let day = match value.to_lowercase().as_str() {
+        "today" => Some(start_of_today()),
+        "yesterday" => Some(start_of_today() - Duration::days(1)),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc()),
+    };
+    if let Some(start) = day {
+        let end = start + Duration::days(1);
+        return match comparison {
+            Comparison::Eq => range(Some((start, true)), Some((end, false))),
+            Comparison::Gt => range(Some((end, true)), None),
+            Comparison::Ge => range(Some((start, true)), None),
+            Comparison::Lt => range(None, Some((start, false))),
+            Comparison::Le => range(None, Some((end, false))),
+        };
+    }
+    
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        let at = timestamp.with_timezone(&Utc);
+        return match comparison {
+            Comparison::Eq => range(Some((at, true)), Some((at, true))),
+            Comparison::Gt => range(Some((at, false)), None),
+            Comparison::Ge => range(Some((at, true)), None),
+            Comparison::Lt => range(None, Some((at, false))),
+            Comparison::Le => range(None, Some((at, true))),
+        };
+    }
+    
+    // Ages need a unit, so a stray number isn't silently read as days
+    if value.ends_with(|c: char| c.is_ascii_alphabetic()) {
+        if let Ok(age) = parse_duration(value) {
+            let cutoff = Utc::now() - age;
+            return match comparison {
+                Comparison::Eq | Comparison::Le => range(Some((cutoff, true)), None),
+                Comparison::Lt => range(Some((cutoff, false)), None),
+                Comparison::Gt => range(None, Some((cutoff, false))),
+                Comparison::Ge => range(None, Some((cutoff, true))),
+            };
+        }
+    }
+    
+    Err(anyhow::anyhow!(
+        "Invalid date: {}. Use e.g. 2026-01-01, today, or an age like 7d",
+        value
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn word(text: &str) -> Query {
+        Query::Word { text: text.to_string(), prefix: false }
+    }
+    
+    fn error(input: &str) -> String {
+        Query::parse(input).unwrap_err().to_string()
+    }
+    
+    #[test]
+    fn parses_words_phrases_and_negation() {
+        assert_eq!(Query::parse("meeting notes").unwrap(), Query::And(vec![word("meeting"), word("notes")]));
+        assert_eq!(
+            Query::parse("\"release plan\" tag:\"to do\" auth*").unwrap(),
+            Query::And(vec![
+                Query::Phrase("release plan".to_string()),
+                Query::Tag("to do".to_string()),
+                Query::Word { text: "auth".to_string(), prefix: true },
+            ])
+        );
+        assert_eq!(
+            Query::parse("-draft NOT tag:#old").unwrap(),
+            Query::And(vec![
+                Query::Not(Box::new(word("draft"))),
+                Query::Not(Box::new(Query::Tag("old".to_string()))),
+            ])
+        );
+        // A dash on its own or inside a word isn't a negation
+        assert_eq!(Query::parse("a - b").unwrap(), Query::And(vec![word("a"), word("-"), word("b")]));
+        assert_eq!(Query::parse("e-mail").unwrap(), word("e-mail"));
+    }
+    
+    #[test]
+    fn or_binds_looser_than_and() {
+        assert_eq!(
+            Query::parse("a b OR c").unwrap(),
+            Query::Or(vec![Query::And(vec![word("a"), word("b")]), word("c")])
+        );
+        assert_eq!(
+            Query::parse("a AND (b OR c)").unwrap(),
+            Query::And(vec![word("a"), Query::Or(vec![word("b"), word("c")])])
+        );
+        assert_eq!(
+            Query::parse("NOT a OR b").unwrap(),
+            Query::Or(vec![Query::Not(Box::new(word("a"))), word("b")])
+        );
+    }
+    
+    #[test]
+    fn parses_field_filters() {
+        assert_eq!(Query::parse("priority:>=high").unwrap(), Query::Priority(Comparison::Ge, Priority::High));
+        assert_eq!(Query::parse("PRIORITY:u").unwrap(), Query::Priority(Comparison::Eq, Priority::Urgent));
+        assert_eq!(Query::parse("archived:yes").unwrap(), Query::Archived(true));
+        assert_eq!(Query::parse("notebook:Work").unwrap(), Query::Notebook("Work".to_string()));
+        assert!(Query::parse("tag:work notebook:home").unwrap().mentions("notebook"));
+        assert!(!Query::parse("tag:work").unwrap().mentions("archived"));
+    }
+    
+    #[test]
+    fn parses_dates_and_ages() {
+        let day = |date: &str| NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc();
+        
+        assert_eq!(
+            Query::parse("created:2026-01-01").unwrap(),
+            Query::Created(TimeRange { after: Some((day("2026-01-01"), true)), before: Some((day("2026-01-02"), false)) })
+        );
+        assert_eq!(
+            Query::parse("updated:>2026-01-01").unwrap(),
+            Query::Updated(TimeRange { after: Some((day("2026-01-02"), true)), before: None })
+        );
+        assert_eq!(
+            Query::parse("created:<=2026-01-01").unwrap(),
+            Query::Created(TimeRange { after: None, before: Some((day("2026-01-02"), false)) })
+        );
+        assert_eq!(
+            Query::parse("created:yesterday").unwrap(),
+            Query::Created(TimeRange {
+                after: Some((start_of_today() - Duration::days(1), true)),
+                before: Some((start_of_today(), false)),
+            })
+        );
+        
+        // `<7d` and `7d` mean within the last week, `>7d` longer ago than that
+        let week_ago = Utc::now() - Duration::days(7);
+        let Query::Created(TimeRange { after: Some((cutoff, false)), before: None }) = Query::parse("created:<7d").unwrap() else {
+            panic!("expected a lower bound");
+        };
+        assert!((cutoff - week_ago).num_seconds().abs() < 5);
+        assert!(matches!(Query::parse("created:7d").unwrap(), Query::Created(TimeRange { after: Some((_, true)), before: None })));
+        assert!(matches!(Query::parse("created:>2w").unwrap(), Query::Created(TimeRange { after: None, before: Some((_, false)) })));
+    }
+    
+    #[test]
+    fn reports_what_is_wrong_with_a_query() {
+        assert_eq!(error("   "), "Empty search query");
+        assert_eq!(error("a)"), "Unbalanced ')' in search query");
+        assert_eq!(error("(a OR b"), "Missing ')' in search query");
+        assert_eq!(error("say \"hello"), "Unterminated quote at '\"hello' in search query");
+        assert_eq!(error("a AND"), "Search query ends where a search term was expected");
+        assert_eq!(error("OR a"), "Expected a search term but found OR");
+        assert_eq!(
+            error("user:id"),
+            "Unknown search field 'user'. Use tag, priority, created, updated, archived, notebook, or put the term in quotes to search for it"
+        );
+        assert_eq!(error("tag:"), "Missing value after 'tag:'");
+        assert_eq!(error("tag:>work"), "'tag:' doesn't support <, >, <= or >=");
+        assert_eq!(error("archived:maybe"), "Invalid archived value: maybe. Use yes or no");
+        assert_eq!(error("priority:top"), "Invalid priority: top. Use: low, medium, high, urgent");
+        assert_eq!(error("created:5"), "Invalid date: 5. Use e.g. 2026-01-01, today, or an age like 7d");
+    }
+    
+    #[test]
+    fn literal_takes_every_word_as_text() {
+        assert_eq!(Query::literal("tag:work OR (x)").unwrap(), Query::And(vec![word("tag:work"), word("OR"), word("(x)")]));
+        assert_eq!(Query::literal("C ++").unwrap(), word("C"));
+        assert_eq!(Query::literal("++").unwrap(), word("++"));
+        assert_eq!(Query::literal(" ").unwrap_err().to_string(), "Empty search query");
+    }
+    
+    #[test]
+    fn finds_code_terms() {
+        for code in ["getUserById", "OAuth", "foo-bar", "src/auth/mod.rs", "x_y"] {
+            assert!(looks_like_code(code), "{}", code);
+        }
+        for text in ["meeting", "Meeting.", "don't", "URGENT"] {
+            assert!(!looks_like_code(text), "{}", text);
+        }
+        assert_eq!(
+            Query::parse("getUser -\"old api\"").unwrap().into_substring(),
+            Query::And(vec![
+                Query::Substring("getUser".to_string()),
+                Query::Not(Box::new(Query::Substring("old api".to_string()))),
+            ])
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use rusqlite::types::Value;
+
+use super::{Comparison, Query, TimeRange};
+use crate::models::Priority;
+
+impl Query {
+    /// Compile to a SQL condition on a `notes` table aliased `n`. Parameters
+    /// are appended to `values` and referenced as `?N`, so the condition can
+    /// follow others built the same way. Text terms become full-text matches.
+    pub fn to_sql(&self, values: &mut Vec<Value>) -> String {
+        match self {
+            Query::Word { text, prefix } => {
+                let term = if *prefix { format!("{}*", fts_string(text)) } else { fts_string(text) };
+                fts_match(term, values)
+            }
+            Query::Phrase(phrase) => fts_match(fts_string(phrase), values),
+            // The trigram index can't look up fewer than three characters
+            Query::Substring(text) if text.chars().count() < 3 => {
+                let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                values.push(Value::Text(format!("%{}%", escaped)));
+                format!("n.content LIKE ?{} ESCAPE '\\'", values.len())
+            }
+            Query::Substring(text) => {
+                values.push(Value::Text(fts_string(text)));
+                format!("n.id IN (SELECT rowid FROM notes_trigram WHERE notes_trigram MATCH ?{})", values.len())
+            }
+            Query::Tag(tag) => {
+                values.push(Value::Text(tag.clone()));
+                format!(
+                    "n.id IN (SELECT nt.note_id FROM note_tags nt JOIN tags t ON nt.tag_id = t.id WHERE t.name = ?{})",
+                    values.len()
+                )
+            }
+            Query::Priority(comparison, priority) => {
+                values.push(Value::Integer(rank(priority)));
+                format!(
+                    "(CASE n.priority WHEN 'low' THEN 1 WHEN 'medium' THEN 2 WHEN 'high' THEN 3 WHEN 'urgent' THEN 4 END) {} ?{}",
+                    operator(*comparison),
+                    values.len()
+                )
+            }
+            Query::Created(range) => time_condition("n.created_at", range, values),
+            Query::Updated(range) => time_condition("n.updated_at", range, values),
+            Query::Archived(archived) => format!("n.is_archived = {}", *archived as i32),
+            Query::Notebook(name) => {
+                values.push(Value::Text(name.clone()));
+                format!("n.notebook_id IN (SELECT id FROM notebooks WHERE name = ?{})", values.len())
+            }
+            Query::Raw(expression) => fts_match(expression.clone(), values),
+            Query::Not(inner) => format!("NOT ({})", inner.to_sql(values)),
+            Query::And(parts) => join(parts, " AND ", values),
+            Query::Or(parts) => join(parts, " OR ", values),
+        }
+    }
+    
+    /// The words and phrases the query looks for, outside any `NOT`, as one
+    /// FTS5 expression matching any of them. `None` when it only has filters.
+    pub fn fts_expression(&self) -> Option<String> {
+        let mut terms = Vec::new();
+        self.collect_text(&mut |query| match query {
+            Query::Word { text, prefix: true } => terms.push(format!("{}*", fts_string(text))),
+            Query::Word { text, prefix: false } | Query::Phrase(text) => terms.push(fts_string(text)),
+            Query::Raw(expression) => terms.push(expression.clone()),
+            _ => {}
+        });
+        (!terms.is_empty()).then(|| terms.join(" OR "))
+    }
+    
+    /// Like `fts_expression`, for the substrings the query looks for, as an
+    /// expression on the trigram index.
+    pub fn substring_expression(&self) -> Option<String> {
+        let mut terms = Vec::new();
+        self.collect_text(&mut |query| match query {
+            Query::Substring(text) if text.chars().count() >= 3 => terms.push(fts_string(text)),
+            _ => {}
+        });
+        (!terms.is_empty()).then(|| terms.join(" OR "))
+    }
+    
+    /// The text of the words, phrases and substrings the query looks for, outside any `NOT`.
+    pub fn text_terms(&self) -> Vec<String> {
+        let mut terms = Vec::new();
+        self.collect_text(&mut |query| {
+            if let Query::Word { text, .. } | Query::Phrase(text) | Query::Substring(text) = query {
+                terms.push(text.clone());
+            }
+        });
+        terms
+    }
+    
+    fn collect_text(&self, f: &mut impl FnMut(&Query)) {
+        match self {
+            Query::Not(_) => {}
+            Query::And(parts) | Query::Or(parts) => {
+                for part in parts {
+                    part.collect_text(f);
+                }
+            }
+            term => f(term),
+        }
+    }
+}
+
+fn join(parts: &[Query], separator: &str, values: &mut Vec<Value>) -> String {
+    let conditions: Vec<String> = parts.iter().map(|part| format!("({})", part.to_sql(values))).collect();
+    conditions.join(separator)
+}
+
+fn fts_match(term: String, values: &mut Vec<Value>) -> String {
+    values.push(Value::Text(term));
+    format!("n.id IN (SELECT rowid FROM notes_fts WHERE notes_fts MATCH ?{})", values.len())
+}
+
+/// An FTS5 string literal, so punctuation in the text can't be read as query syntax.
+fn fts_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+fn time_condition(column: &str, range: &TimeRange, values: &mut Vec<Value>) -> String {
+    let mut conditions = Vec::new();
+    if let Some((after, inclusive)) = range.after {
+        values.push(Value::Text(after.to_rfc3339()));
+        conditions.push(format!("{} {} ?{}", column, if inclusive { ">=" } else { ">" }, values.len()));
+    }
+    if let Some((before, inclusive)) = range.before {
+        values.push(Value::Text(before.to_rfc3339()));
+        conditions.push(format!("{} {} ?{}", column, if inclusive { "<=" } else { "<" }, values.len()));
+    }
+    conditions.join(" AND ")
+}
+
+fn operator(comparison: Comparison) -> &'static str {
+    match comparison {
+        Comparison::Eq => "=",
+        Comparison::Lt => "<",
+        Comparison::Le => "<=",
+        Comparison::Gt => ">",
+        Comparison::Ge => ">=",
+    }
+}
+
+fn rank(priority: &Priority) -> i64 {
+    match priority {
+        Priority::Low => 1,
+        Priority::Medium => 2,
+        Priority::High => 3,
+        Priority::Urgent => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sql(input: &str) -> (String, Vec<Value>) {
+        let mut values = Vec::new();
+        let sql = Query::parse(input).unwrap().to_sql(&mut values);
+        (sql, values)
+    }
+    
+    fn text(value: &str) -> Value {
+        Value::Text(value.to_string())
+    }
+    
+    #[test]
+    fn compiles_terms_to_parameterized_conditions() {
+        let (condition, values) = sql("tag:work -draft");
+        assert_eq!(
+            condition,
+            "(n.id IN (SELECT nt.note_id FROM note_tags nt JOIN tags t ON nt.tag_id = t.id WHERE t.name = ?1)) AND \
+             (NOT (n.id IN (SELECT rowid FROM notes_fts WHERE notes_fts MATCH ?2)))"
+        );
+        assert_eq!(values, vec![text("work"), text("\"draft\"")]);
+        
+        let (condition, values) = sql("a OR priority:>=high");
+        assert_eq!(
+            condition,
+            "(n.id IN (SELECT rowid FROM notes_fts WHERE notes_fts MATCH ?1)) OR \
+             ((CASE n.priority WHEN 'low' THEN 1 WHEN 'medium' THEN 2 WHEN 'high' THEN 3 WHEN 'urgent' THEN 4 END) >= ?2)"
+        );
+        assert_eq!(values, vec![text("\"a\""), Value::Integer(3)]);
+        
+        assert_eq!(sql("archived:no").0, "n.is_archived = 0");
+    }
+    
+    #[test]
+    fn numbers_parameters_after_existing_ones() {
+        let mut values = vec![Value::Integer(1)];
+        let condition = Query::parse("notebook:Work").unwrap().to_sql(&mut values);
+        assert_eq!(condition, "n.notebook_id IN (SELECT id FROM notebooks WHERE name = ?2)");
+        assert_eq!(values, vec![Value::Integer(1), text("Work")]);
+    }
+    
+    #[test]
+    fn quotes_text_for_fts() {
+        assert_eq!(sql("say\"so").1, vec![text("\"say\"\"so\"")]);
+        assert_eq!(sql("auth*").1, vec![text("\"auth\"*")]);
+        assert_eq!(sql("\"NEAR(a b)\"").1, vec![text("\"NEAR(a b)\"")]);
+        
+        let query = Query::parse("a OR \"b c\" d* -e tag:x").unwrap();
+        assert_eq!(query.fts_expression().as_deref(), Some("\"a\" OR \"b c\" OR \"d\"*"));
+        assert_eq!(query.text_terms(), vec!["a", "b c", "d"]);
+        assert_eq!(Query::parse("tag:x -e").unwrap().fts_expression(), None);
+    }
+    
+    #[test]
+    fn compiles_date_bounds() {
+        let (condition, values) = sql("created:2026-01-01");
+        assert_eq!(condition, "n.created_at >= ?1 AND n.created_at < ?2");
+        assert_eq!(values, vec![text("2026-01-01T00:00:00+00:00"), text("2026-01-02T00:00:00+00:00")]);
+        
+        assert_eq!(sql("updated:<2026-01-01").0, "n.updated_at < ?1");
+    }
+    
+    #[test]
+    fn matches_short_substrings_with_like() {
+        let mut values = Vec::new();
+        let condition = Query::Substring("%_".to_string()).to_sql(&mut values);
+        assert_eq!(condition, "n.content LIKE ?1 ESCAPE '\\'");
+        assert_eq!(values, vec![text("%\\%\\_%")]);
+        
+        let query = Query::parse("auth").unwrap().into_substring();
+        assert_eq!(query.to_sql(&mut values), "n.id IN (SELECT rowid FROM notes_trigram WHERE notes_trigram MATCH ?2)");
+        assert_eq!(query.substring_expression().as_deref(), Some("\"auth\""));
+        assert_eq!(Query::Substring("ab".to_string()).substring_expression(), None);
+    }
+}
@@ -0,0 +1,122 @@
+use rusqlite::params;
+
+use crate::query::Query;
+use crate::utils::edit_distance;
+
+use super::NoteService;
+
+/// How many other spellings of a word `ezn search --fuzzy` also looks for.
+const MAX_ALTERNATIVES: usize = 5;
+
+impl NoteService {
+    /// `query` with each word also matching the indexed words a typo or two
+    /// away from it, so misspellings in either the query or the notes match.
+    pub fn fuzzy_query(&self, query: Query) -> anyhow::Result<Query> {
+        query.expand_words(&mut |word| {
+            let word = word.to_lowercase();
+            Ok(self.similar_terms(&word)?
+                .into_iter()
+                .filter(|term| *term != word)
+                .take(MAX_ALTERNATIVES)
+                .collect())
+        })
+    }
+    
+    /// For each word in `query` that appears in no note, the closest word
+    /// that does, as `(word, suggestion)` pairs.
+    pub fn spelling_suggestions(&self, query: &Query) -> anyhow::Result<Vec<(String, String)>> {
+        let mut suggestions = Vec::new();
+        for word in query.words() {
+            let lowercase = word.to_lowercase();
+            let terms = self.similar_terms(&lowercase)?;
+            if terms.contains(&lowercase) {
+                continue;
+            }
+            if let Some(term) = terms.into_iter().next() {
+                suggestions.push((word.to_string(), term));
+            }
+        }
+        Ok(suggestions)
+    }
+    
+    /// Words in the full-text index within one typo of a lowercase `word`,
+    /// or two for words of eight letters or more, closest and then most used
+    /// first. Includes `word` itself when it is indexed.
+    fn similar_terms(&self, word: &str) -> anyhow::Result<Vec<String>> {
+        let length = word.chars().count();
+        let max_distance = match length {
+            0..=2 => return Ok(Vec::new()),
+            3..=7 => 1,
+            _ => 2,
+        };
+        // Anything else is split into several words by the tokenizer
+        if !word.chars().all(char::is_alphanumeric) {
+            return Ok(Vec::new());
+        }
+        
+        let conn = self.db.connection();
+        // The index's vocabulary, as a temporary table so it stays out of the schema
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS temp.notes_vocab USING fts5vocab(main, notes_fts, row);"
+        )?;
+        let mut stmt = conn.prepare("SELECT term, doc FROM temp.notes_vocab WHERE length(term) BETWEEN ?1 AND ?2")?;
+        let rows = stmt.query_map(
+            params![(length - max_distance) as i64, (length + max_distance) as i64],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        
+        let mut candidates = Vec::new();
+        for row in rows {
+            let (term, notes) = row?;
+            let distance = edit_distance(word, &term);
+            if distance <= max_distance {
+                candidates.push((distance, -notes, term));
+            }
+        }
+        candidates.sort();
+        
+        Ok(candidates.into_iter().map(|(_, _, term)| term).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{NoteFilter, Priority, SearchOrder};
+    use crate::query::Query;
+    use crate::services::test_service;
+    
+    fn word(text: &str) -> Query {
+        Query::Word { text: text.to_string(), prefix: false }
+    }
+    
+    #[test]
+    fn finds_indexed_words_a_typo_away() {
+        let (_dir, service) = test_service();
+        for content in ["Weekly meeting with the team", "Meeting notes", "Mating season", "Recommendations"] {
+            service.add_note(content, Vec::new(), Priority::Medium, None).unwrap();
+        }
+        
+        assert_eq!(service.similar_terms("meetnig").unwrap(), vec!["meeting"]);
+        // Equally close words come most used first
+        assert_eq!(service.similar_terms("meting").unwrap(), vec!["meeting", "mating"]);
+        assert_eq!(service.similar_terms("teh").unwrap(), vec!["the"]);
+        // Long words allow two typos, short ones none at all
+        assert_eq!(service.similar_terms("recomendatons").unwrap(), vec!["recommendations"]);
+        assert!(service.similar_terms("te").unwrap().is_empty());
+        assert!(service.similar_terms("meet-ing").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn fuzzy_queries_and_suggestions_use_the_index() {
+        let (_dir, service) = test_service();
+        service.add_note("Weekly meeting with the team", Vec::new(), Priority::Medium, None).unwrap();
+        
+        let query = service.fuzzy_query(Query::parse("Meetnig team").unwrap()).unwrap();
+        assert_eq!(query, Query::And(vec![Query::Or(vec![word("Meetnig"), word("meeting")]), word("team")]));
+        let found = service.search(&query, &NoteFilter::default(), SearchOrder::Newest).unwrap();
+        assert_eq!(found.results.len(), 1);
+        
+        let suggestions = service.spelling_suggestions(&Query::parse("Meetnig team -wekly").unwrap()).unwrap();
+        assert_eq!(suggestions, vec![("Meetnig".to_string(), "meeting".to_string())]);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::models::{Note, Priority, Revision};
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+use super::NoteService;
+
+impl NoteService {
+    pub fn list_revisions(&self, note_id: i64) -> anyhow::Result<Vec<Revision>> {
+        self.get_note(note_id)?;
+        
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(
+            "SELECT note_id, revision, content, priority, tags, created_at
+             FROM note_revisions
+             WHERE note_id = ?1
+             ORDER BY revision ASC"
+        )?;
+        
+        let revisions = stmt.query_map(params![note_id], |row| {
+            Ok(Revision {
+                note_id: row.get(0)?,
+                revision: row.get(1)?,
+                content: row.get(2)?,
+                priority: Priority::from_str(&row.get::<_, String>(3)?).unwrap(),
+                tags: serde_json::from_str(&row.get::<_, String>(4)?).unwrap_or_default(),
+                created_at: row.get::<_, String>(5)?.parse().unwrap(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(revisions)
+    }
+    
+    pub fn get_revision(&self, note_id: i64, revision: i64) -> anyhow::Result<Revision> {
+        self.list_revisions(note_id)?
+            .into_iter()
+            .find(|r| r.revision == revision)
+            .ok_or_else(|| anyhow::anyhow!("Note {} has no revision {}", note_id, revision))
+    }
+    
+    /// Restore a note's content, priority and tags to an earlier revision.
+    /// The rollback itself is recorded as a new revision.
+    pub fn revert_note(&self, note_id: i64, revision: i64) -> anyhow::Result<Note> {
+        self.operation("revert", || {
+            let target = self.get_revision(note_id, revision)?;
+            let current = self.get_note(note_id)?;
+            
+            let add_tags: Vec<String> = target.tags.iter()
+                .filter(|t| !current.tags.contains(t))
+                .cloned()
+                .collect();
+            let remove_tags: Vec<String> = current.tags.iter()
+                .filter(|t| !target.tags.contains(t))
+                .cloned()
+                .collect();
+            
+            let reverted = self.update_note(
+                note_id,
+                Some(&target.content),
+                Some(target.priority),
+                &add_tags,
+                &remove_tags,
+            )?;
+            Ok(reverted.unwrap_or(current))
+        })
+    }
+    
+    /// Snapshot the note's current state as a new revision, unless nothing changed
+    /// since the latest one.
+    pub(super) fn record_revision(&self, note_id: i64) -> anyhow::Result<()> {
+        let note = match self.find_note(note_id)? {
+            Some(note) => note,
+            None => return Ok(()),
+        };
+        let mut tags = note.tags.clone();
+        tags.sort();
+        
+        let conn = self.db.connection();
+        let latest: Option<(i64, String, String, String)> = conn.query_row(
+            "SELECT revision, content, priority, tags FROM note_revisions
+             WHERE note_id = ?1 ORDER BY revision DESC LIMIT 1",
+            params![note_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        ).optional()?;
+        
+        let tags_json = serde_json::to_string(&tags)?;
+        
+        let next = match latest {
+            Some((rev, content, priority, latest_tags)) => {
+                if content == note.content && priority == note.priority.to_string() && latest_tags == tags_json {
+                    return Ok(());
+                }
+                rev + 1
+            }
+            None => 1,
+        };
+        
+        // The first snapshot of a pre-existing note is dated from its last update
+        let created_at = if next == 1 { note.updated_at } else { Utc::now() };
+        
+        conn.execute(
+            "INSERT INTO note_revisions (note_id, revision, content, priority, tags, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![note_id, next, note.content, note.priority.to_string(), tags_json, created_at.to_rfc3339()],
+        )?;
+        
+        Ok(())
+    }
+}
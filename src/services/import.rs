@@ -0,0 +1,159 @@
+use crate::models::{ImportSummary, Note, LINK_SCHEME};
+use rusqlite::params;
+use std::collections::HashMap;
+
+use super::NoteService;
+
+impl NoteService {
+    /// Recreate `notes` with their original timestamps, priority, archive flag
+    /// and tags, all as one undoable operation. Notes get fresh IDs, and
+    /// `ezn:ID` links between them are rewritten to match; notes whose content
+    /// (ignoring link targets) and `created_at` match an existing note are
+    /// skipped. Each note goes into its own notebook (created if missing), or
+    /// `notebook_id` / the current notebook when it has none.
+    pub fn import_notes(&self, notes: &[Note], notebook_id: Option<i64>) -> anyhow::Result<ImportSummary> {
+        self.operation("import", || {
+            let default_notebook = match notebook_id {
+                Some(id) => id,
+                None => self.current_notebook()?.id,
+            };
+            let conn = self.db.connection();
+            let mut summary = ImportSummary::default();
+            // Where each incoming ID ended up, including duplicates of existing notes
+            let mut id_map = HashMap::new();
+            let mut inserted = Vec::new();
+            
+            for note in notes {
+                if note.content.trim().is_empty() {
+                    summary.skipped_empty += 1;
+                    continue;
+                }
+                
+                let created_at = note.created_at.to_rfc3339();
+                if let Some(existing) = self.find_duplicate(&note.content, &created_at)? {
+                    if let Some(old_id) = note.id {
+                        id_map.insert(old_id, existing);
+                    }
+                    summary.duplicates += 1;
+                    continue;
+                }
+                
+                let notebook_id = match (notebook_id, &note.notebook) {
+                    (None, Some(name)) => self.ensure_notebook(name)?,
+                    _ => default_notebook,
+                };
+                
+                conn.execute(
+                    "INSERT INTO notes (content, priority, created_at, updated_at, is_archived, deleted_at, notebook_id)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                    params![
+                        note.content,
+                        note.priority.to_string(),
+                        created_at,
+                        note.updated_at.to_rfc3339(),
+                        note.is_archived as i32,
+                        note.deleted_at.map(|d| d.to_rfc3339()),
+                        notebook_id,
+                    ],
+                )?;
+                
+                let new_id = conn.last_insert_rowid();
+                self.track_created(new_id);
+                
+                for tag in &note.tags {
+                    self.add_tag_to_note(new_id, tag)?;
+                }
+                
+                if let Some(old_id) = note.id {
+                    id_map.insert(old_id, new_id);
+                }
+                summary.imported.push((note.id, new_id));
+                inserted.push((note, new_id));
+            }
+            
+            // Links can point forwards, so rewrite them once every note has its new ID
+            for (note, new_id) in inserted {
+                let content = rewrite_links(&note.content, |id| id_map.get(&id).map(|new| new.to_string()));
+                if content != note.content {
+                    conn.execute("UPDATE notes SET content = ?1 WHERE id = ?2", params![content, new_id])?;
+                }
+                self.record_revision(new_id)?;
+            }
+            
+            Ok(summary)
+        })
+    }
+    
+    /// An existing note created at `created_at` with the same content. Link
+    /// targets are ignored, since an earlier import will have rewritten them.
+    fn find_duplicate(&self, content: &str, created_at: &str) -> anyhow::Result<Option<i64>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare("SELECT id, content FROM notes WHERE created_at = ?1 ORDER BY id")?;
+        let candidates = stmt.query_map(params![created_at], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let unlinked = rewrite_links(content, |_| Some(String::new()));
+        Ok(candidates.into_iter()
+            .find(|(_, existing)| rewrite_links(existing, |_| Some(String::new())) == unlinked)
+            .map(|(id, _)| id))
+    }
+}
+
+/// Replace the target of each `](ezn:ID)` link with `f(ID)`, leaving links
+/// where `f` returns `None` alone. IDs may be negative, as importers give
+/// notes without one of their own.
+fn rewrite_links(content: &str, f: impl Fn(i64) -> Option<String>) -> String {
+    let marker = format!("]({}", LINK_SCHEME);
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    
+    while let Some(pos) = rest.find(&marker) {
+        let (before, after) = rest.split_at(pos + marker.len());
+        out.push_str(before);
+        
+        let sign = usize::from(after.starts_with('-'));
+        let digits = after[sign..].find(|c: char| !c.is_ascii_digit()).map_or(after.len(), |end| sign + end);
+        let (number, tail) = after.split_at(digits);
+        match number.parse::<i64>().ok().and_then(&f) {
+            Some(target) if tail.starts_with(')') => out.push_str(&target),
+            _ => out.push_str(number),
+        }
+        rest = tail;
+    }
+    
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::markdown;
+    use crate::import::obsidian;
+    use crate::models::Priority;
+    use crate::services::test_service;
+    
+    #[test]
+    fn reimported_markdown_keeps_links_between_notes() {
+        let (_dir, source) = test_service();
+        source.add_note("Unrelated", Vec::new(), Priority::Medium, None).unwrap();
+        let alpha = source.add_note("Alpha", Vec::new(), Priority::Medium, None).unwrap();
+        let see = source.add_note("See [alpha](ezn:2)", Vec::new(), Priority::Medium, None).unwrap();
+        
+        let export = tempfile::tempdir().unwrap();
+        markdown::write_notes(&[alpha, see], export.path()).unwrap();
+        // A file from elsewhere linking to an exported one by name
+        std::fs::write(export.path().join("Gamma.md"), "Back to [[2-alpha|Alpha]]").unwrap();
+        
+        let (_dir, target) = test_service();
+        target.add_note("Already here", Vec::new(), Priority::Medium, None).unwrap();
+        let batch = obsidian::read_vault(export.path()).unwrap();
+        assert!(batch.failures.is_empty(), "{:?}", batch.failures);
+        let summary = target.import_notes(&batch.notes, None).unwrap();
+        
+        let new_id = |old: i64| summary.imported.iter().find(|(id, _)| *id == Some(old)).unwrap().1;
+        let alpha_id = new_id(2);
+        assert_eq!(target.get_note(alpha_id).unwrap().content, "Alpha");
+        assert_eq!(target.get_note(new_id(3)).unwrap().content, format!("See [alpha](ezn:{})", alpha_id));
+        assert_eq!(target.get_note(new_id(-1)).unwrap().content, format!("# Gamma\n\nBack to [Alpha](ezn:{})", alpha_id));
+    }
+}
@@ -0,0 +1,381 @@
+use crate::models::{Note, Notebook, Operation};
+use chrono::Utc;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use super::NoteService;
+
+/// How many operations to keep around for `ezn undo`.
+const JOURNAL_LIMIT: i64 = 200;
+
+/// Notes touched by the operation in progress, with their state before it started.
+/// A `None` state means the note did not exist yet. Notebooks it created are
+/// kept alongside.
+pub(super) struct PendingOperation {
+    command: String,
+    changes: Vec<(i64, Option<String>)>,
+    notebooks: Vec<Notebook>,
+}
+
+/// A note as the journal records it. The notebook is kept by ID, so renaming
+/// it doesn't detach the note; the name is the fallback for notebooks deleted
+/// since, and for entries written before IDs were recorded.
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    #[serde(flatten)]
+    note: Note,
+    #[serde(default)]
+    notebook_id: Option<i64>,
+}
+
+impl NoteService {
+    /// Invert the last `count` operations, newest first.
+    pub fn undo(&self, count: usize) -> anyhow::Result<Vec<Operation>> {
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+        
+        let operations = self.load_operations(
+            "SELECT id, command, created_at FROM operations WHERE undone = 0 ORDER BY id DESC LIMIT ?1",
+            count,
+        )?;
+        
+        for op in &operations {
+            let changes = self.load_changes(op.id, "before_state")?;
+            for (note_id, state) in changes.iter().rev() {
+                self.apply_state(*note_id, state.as_deref())?;
+            }
+            self.remove_created_notebooks(op.id)?;
+            conn.execute("UPDATE operations SET undone = 1 WHERE id = ?1", params![op.id])?;
+        }
+        
+        tx.commit()?;
+        Ok(operations)
+    }
+    
+    /// Replay the last `count` undone operations, oldest first.
+    pub fn redo(&self, count: usize) -> anyhow::Result<Vec<Operation>> {
+        let conn = self.db.connection();
+        let tx = conn.unchecked_transaction()?;
+        
+        let operations = self.load_operations(
+            "SELECT id, command, created_at FROM operations WHERE undone = 1 ORDER BY id ASC LIMIT ?1",
+            count,
+        )?;
+        
+        for op in &operations {
+            self.restore_created_notebooks(op.id)?;
+            for (note_id, state) in self.load_changes(op.id, "after_state")? {
+                self.apply_state(note_id, state.as_deref())?;
+            }
+            conn.execute("UPDATE operations SET undone = 0 WHERE id = ?1", params![op.id])?;
+        }
+        
+        tx.commit()?;
+        Ok(operations)
+    }
+    
+    /// Run `f` as a single undoable operation inside a transaction. Nested calls
+    /// (e.g. `revert_note` calling `update_note`) join the outermost operation.
+    pub(super) fn operation<T>(&self, command: &str, f: impl FnOnce() -> anyhow::Result<T>) -> anyhow::Result<T> {
+        if self.pending.borrow().is_some() {
+            return f();
+        }
+        
+        let tx = self.db.connection().unchecked_transaction()?;
+        *self.pending.borrow_mut() = Some(PendingOperation {
+            command: command.to_string(),
+            changes: Vec::new(),
+            notebooks: Vec::new(),
+        });
+        
+        let result = f();
+        let pending = self.pending.borrow_mut().take()
+            .expect("pending operation disappeared");
+        
+        // Dropping the transaction on error rolls everything back
+        let value = result?;
+        self.write_operation(pending)?;
+        tx.commit()?;
+        
+        Ok(value)
+    }
+    
+    /// Remember the current state of `id` before the running operation changes it.
+    pub(super) fn track(&self, id: i64) -> anyhow::Result<()> {
+        if self.is_tracked(id) {
+            return Ok(());
+        }
+        
+        let state = self.snapshot(id)?;
+        if let Some(pending) = self.pending.borrow_mut().as_mut() {
+            pending.changes.push((id, state));
+        }
+        Ok(())
+    }
+    
+    /// Record that the running operation created `id`.
+    pub(super) fn track_created(&self, id: i64) {
+        if let Some(pending) = self.pending.borrow_mut().as_mut() {
+            pending.changes.push((id, None));
+        }
+    }
+    
+    /// Record that the running operation created `notebook`.
+    pub(super) fn track_created_notebook(&self, notebook: &Notebook) {
+        if let Some(pending) = self.pending.borrow_mut().as_mut() {
+            pending.notebooks.push(notebook.clone());
+        }
+    }
+    
+    /// Drop journal entries for a purged note so undo can't resurrect it.
+    pub(super) fn forget_note(&self, id: i64) -> anyhow::Result<()> {
+        let conn = self.db.connection();
+        conn.execute("DELETE FROM operation_changes WHERE note_id = ?1", params![id])?;
+        conn.execute(
+            "DELETE FROM operations WHERE id NOT IN (SELECT operation_id FROM operation_changes)",
+            [],
+        )?;
+        Ok(())
+    }
+    
+    fn is_tracked(&self, id: i64) -> bool {
+        self.pending.borrow()
+            .as_ref()
+            .is_none_or(|pending| pending.changes.iter().any(|(tracked, _)| *tracked == id))
+    }
+    
+    fn write_operation(&self, pending: PendingOperation) -> anyhow::Result<()> {
+        let mut changes = Vec::new();
+        for (id, before) in pending.changes {
+            let after = self.snapshot(id)?;
+            if before != after {
+                changes.push((id, before, after));
+            }
+        }
+        
+        if changes.is_empty() && pending.notebooks.is_empty() {
+            return Ok(());
+        }
+        
+        let conn = self.db.connection();
+        
+        // A new change invalidates anything that could have been redone
+        conn.execute("DELETE FROM operations WHERE undone = 1", [])?;
+        
+        conn.execute(
+            "INSERT INTO operations (command, created_at) VALUES (?1, ?2)",
+            params![pending.command, Utc::now().to_rfc3339()],
+        )?;
+        let operation_id = conn.last_insert_rowid();
+        
+        for (id, before, after) in changes {
+            conn.execute(
+                "INSERT INTO operation_changes (operation_id, note_id, before_state, after_state)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![operation_id, id, before, after],
+            )?;
+        }
+        
+        for notebook in pending.notebooks {
+            conn.execute(
+                "INSERT INTO operation_notebooks (operation_id, notebook_id, name, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![operation_id, notebook.id, notebook.name, notebook.created_at.to_rfc3339()],
+            )?;
+        }
+        
+        conn.execute(
+            "DELETE FROM operations WHERE id <= ?1",
+            params![operation_id - JOURNAL_LIMIT],
+        )?;
+        
+        Ok(())
+    }
+    
+    fn snapshot(&self, id: i64) -> anyhow::Result<Option<String>> {
+        let Some(note) = self.find_note(id)? else {
+            return Ok(None);
+        };
+        let notebook_id = self.db.connection().query_row(
+            "SELECT notebook_id FROM notes WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )?;
+        Ok(Some(serde_json::to_string(&Snapshot { note, notebook_id })?))
+    }
+    
+    /// Make note `id` match a journaled snapshot, re-creating or deleting it as needed.
+    fn apply_state(&self, id: i64, state: Option<&str>) -> anyhow::Result<()> {
+        let conn = self.db.connection();
+        
+        let Snapshot { note, notebook_id } = match state {
+            Some(json) => serde_json::from_str(json)?,
+            None => {
+                conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+                return Ok(());
+            }
+        };
+        
+        // The note's notebook may have been deleted since; bring it back by name if so
+        let notebook_id = match (notebook_id, &note.notebook) {
+            (Some(notebook_id), _) if self.notebook_exists(notebook_id)? => Some(notebook_id),
+            (_, Some(name)) => Some(self.ensure_notebook(name)?),
+            (_, None) => None,
+        };
+        
+        conn.execute(
+            "INSERT INTO notes (id, content, priority, created_at, updated_at, is_archived, deleted_at, notebook_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(id) DO UPDATE SET
+                content = excluded.content,
+                priority = excluded.priority,
+                created_at = excluded.created_at,
+                updated_at = excluded.updated_at,
+                is_archived = excluded.is_archived,
+                deleted_at = excluded.deleted_at,
+                notebook_id = excluded.notebook_id",
+            params![
+                id,
+                note.content,
+                note.priority.to_string(),
+                note.created_at.to_rfc3339(),
+                note.updated_at.to_rfc3339(),
+                note.is_archived as i32,
+                note.deleted_at.map(|d| d.to_rfc3339()),
+                notebook_id,
+            ],
+        )?;
+        
+        conn.execute("DELETE FROM note_tags WHERE note_id = ?1", params![id])?;
+        for tag in &note.tags {
+            self.add_tag_to_note(id, tag)?;
+        }
+        
+        self.record_revision(id)
+    }
+    
+    /// Delete the notebooks an operation created, unless notes have been put
+    /// in them since or one has become the current notebook.
+    fn remove_created_notebooks(&self, operation_id: i64) -> anyhow::Result<()> {
+        let current = self.current_notebook().ok().map(|notebook| notebook.id);
+        self.db.connection().execute(
+            "DELETE FROM notebooks
+             WHERE id IN (SELECT notebook_id FROM operation_notebooks WHERE operation_id = ?1)
+               AND id IS NOT ?2
+               AND NOT EXISTS (SELECT 1 FROM notes WHERE notes.notebook_id = notebooks.id)",
+            params![operation_id, current],
+        )?;
+        Ok(())
+    }
+    
+    /// Bring back the notebooks an operation created, with their original IDs
+    /// so the notes being redone find them. One that was re-created by name in
+    /// the meantime is left as it is.
+    fn restore_created_notebooks(&self, operation_id: i64) -> anyhow::Result<()> {
+        self.db.connection().execute(
+            "INSERT OR IGNORE INTO notebooks (id, name, created_at)
+             SELECT notebook_id, name, created_at FROM operation_notebooks WHERE operation_id = ?1",
+            params![operation_id],
+        )?;
+        Ok(())
+    }
+    
+    fn load_operations(&self, sql: &str, count: usize) -> anyhow::Result<Vec<Operation>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(sql)?;
+        
+        let operations = stmt.query_map(params![count], |row| {
+            Ok(Operation {
+                id: row.get(0)?,
+                command: row.get(1)?,
+                note_ids: Vec::new(),
+                created_at: row.get::<_, String>(2)?.parse().unwrap(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        operations.into_iter()
+            .map(|mut op| {
+                op.note_ids = self.load_changes(op.id, "after_state")?
+                    .into_iter()
+                    .map(|(id, _)| id)
+                    .collect();
+                Ok(op)
+            })
+            .collect()
+    }
+    
+    fn load_changes(&self, operation_id: i64, column: &str) -> anyhow::Result<Vec<(i64, Option<String>)>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT note_id, {} FROM operation_changes WHERE operation_id = ?1 ORDER BY id ASC",
+            column
+        ))?;
+        
+        let changes = stmt.query_map(params![operation_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::Priority;
+    use crate::services::test_service;
+    
+    #[test]
+    fn undo_after_notebook_rename_keeps_the_renamed_notebook() {
+        let (_dir, service) = test_service();
+        let work = service.create_notebook("work").unwrap();
+        let note = service.add_note("x", Vec::new(), Priority::Medium, Some(work.id)).unwrap();
+        let id = note.id.unwrap();
+        service.update_note(id, None, Some(Priority::High), &[], &[]).unwrap();
+        service.rename_notebook("work", "job").unwrap();
+        
+        service.undo(1).unwrap();
+        
+        let note = service.get_note(id).unwrap();
+        assert_eq!(note.priority, Priority::Medium);
+        assert_eq!(note.notebook.as_deref(), Some("job"));
+        let notebooks: Vec<_> = service.list_notebooks().unwrap().into_iter().map(|b| (b.name, b.note_count)).collect();
+        assert!(notebooks.contains(&("job".to_string(), 1)));
+        assert!(!notebooks.iter().any(|(name, _)| name == "work"));
+    }
+    
+    #[test]
+    fn undo_brings_back_a_deleted_notebook_by_name() {
+        let (_dir, service) = test_service();
+        let work = service.create_notebook("work").unwrap();
+        let id = service.add_note("x", Vec::new(), Priority::Medium, Some(work.id)).unwrap().id.unwrap();
+        service.delete_notebook("work", Some("default")).unwrap();
+        assert_eq!(service.get_note(id).unwrap().notebook.as_deref(), Some("default"));
+        
+        service.undo(1).unwrap();
+        
+        assert_eq!(service.get_note(id).unwrap().notebook.as_deref(), Some("work"));
+    }
+    
+    #[test]
+    fn undoing_an_import_removes_the_notebooks_it_created() {
+        let (_dir, service) = test_service();
+        let mut note = service.add_note("Pack the tent", Vec::new(), Priority::Medium, None).unwrap();
+        note.id = None;
+        note.content = "Book the ferry".to_string();
+        note.notebook = Some("travel".to_string());
+        let notebook_names = |service: &crate::services::NoteService| -> Vec<String> {
+            service.list_notebooks().unwrap().into_iter().map(|b| b.name).collect()
+        };
+        
+        let summary = service.import_notes(&[note], None).unwrap();
+        let id = summary.imported[0].1;
+        assert_eq!(notebook_names(&service), vec!["default", "travel"]);
+        
+        service.undo(1).unwrap();
+        assert_eq!(notebook_names(&service), vec!["default"]);
+        
+        service.redo(1).unwrap();
+        assert_eq!(notebook_names(&service), vec!["default", "travel"]);
+        assert_eq!(service.get_note(id).unwrap().notebook.as_deref(), Some("travel"));
+    }
+}
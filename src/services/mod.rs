@@ -1,270 +1,423 @@
 use crate::db::Database;
-use crate::models::{Note, Priority, Stats};
+use crate::models::{Note, NoteFilter, Priority, SearchMode, SearchOrder, SearchResult, SearchResults, Stats};
+use crate::query::Query;
+use crate::utils::start_of_today;
 use chrono::{Utc, Duration};
-use rusqlite::params;
+use rusqlite::{params, params_from_iter, types::Value};
+use std::cell::RefCell;
+
+mod fuzzy;
+mod history;
+mod import;
+mod journal;
+mod notebooks;
+
+use journal::PendingOperation;
+
+/// Columns selected for every note query, in the order `query_notes` expects.
+const NOTE_COLUMNS: &str =
+    "n.id, n.content, n.priority, n.created_at, n.updated_at, n.is_archived, n.deleted_at, \
+     (SELECT name FROM notebooks WHERE id = n.notebook_id)";
 
 pub struct NoteService {
     db: Database,
+    /// The journaled operation currently in progress, if any
+    pending: RefCell<Option<PendingOperation>>,
 }
 
 impl NoteService {
     pub fn new(db: Database) -> Self {
-        NoteService { db }
+        NoteService { db, pending: RefCell::new(None) }
     }
     
-    pub fn add_note(&self, content: &str, tags: Vec<String>, priority: Priority) -> anyhow::Result<Note> {
-        let now = Utc::now();
-        let conn = self.db.connection();
-        
-        // Insert note
-        conn.execute(
-            "INSERT INTO notes (content, priority, created_at, updated_at) VALUES (?1, ?2, ?3, ?4)",
-            params![content, priority.to_string(), now.to_rfc3339(), now.to_rfc3339()],
-        )?;
-        
-        let note_id = conn.last_insert_rowid();
-        
-        // Add tags
-        for tag in &tags {
-            self.add_tag_to_note(note_id, tag)?;
-        }
-        
-        // Return the created note
-        self.get_note(note_id)
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+    
+    /// Add a note to `notebook_id`, or to the current notebook when `None`.
+    pub fn add_note(
+        &self,
+        content: &str,
+        tags: Vec<String>,
+        priority: Priority,
+        notebook_id: Option<i64>,
+    ) -> anyhow::Result<Note> {
+        self.operation("add", || {
+            let now = Utc::now();
+            let notebook_id = match notebook_id {
+                Some(id) => id,
+                None => self.current_notebook()?.id,
+            };
+            let conn = self.db.connection();
+            
+            // Insert note
+            conn.execute(
+                "INSERT INTO notes (content, priority, created_at, updated_at, notebook_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![content, priority.to_string(), now.to_rfc3339(), now.to_rfc3339(), notebook_id],
+            )?;
+            
+            let note_id = conn.last_insert_rowid();
+            self.track_created(note_id);
+            
+            // Add tags
+            for tag in &tags {
+                self.add_tag_to_note(note_id, tag)?;
+            }
+            
+            self.record_revision(note_id)?;
+            
+            // Return the created note
+            self.get_note(note_id)
+        })
     }
     
     pub fn get_note(&self, id: i64) -> anyhow::Result<Note> {
-        let conn = self.db.connection();
-        
-        let mut stmt = conn.prepare(
-            "SELECT id, content, priority, created_at, updated_at, is_archived FROM notes WHERE id = ?1"
-        )?;
-        
-        let note = stmt.query_row(params![id], |row| {
-            Ok(Note {
-                id: Some(row.get(0)?),
-                content: row.get(1)?,
-                priority: Priority::from_str(&row.get::<_, String>(2)?).unwrap(),
-                created_at: row.get::<_, String>(3)?.parse().unwrap(),
-                updated_at: row.get::<_, String>(4)?.parse().unwrap(),
-                is_archived: row.get::<_, i32>(5)? != 0,
-                tags: Vec::new(),
-            })
-        }).map_err(|_| anyhow::anyhow!("Note with ID {} not found", id))?;
+        let note = self.find_note(id)?
+            .ok_or_else(|| anyhow::anyhow!("Note with ID {} not found", id))?;
         
-        // Load tags
-        let tags = self.get_tags_for_note(id)?;
+        if note.deleted_at.is_some() {
+            return Err(anyhow::anyhow!(
+                "Note {} is in the trash (restore it with `ezn restore {}`)", id, id
+            ));
+        }
         
-        Ok(Note { tags, ..note })
+        Ok(note)
     }
     
-    pub fn list_recent(&self, limit: usize) -> anyhow::Result<Vec<Note>> {
-        let conn = self.db.connection();
+    pub fn list_notes(&self, filter: &NoteFilter) -> anyhow::Result<Vec<Note>> {
+        let (conditions, values) = Self::filter_conditions(filter);
         
-        let mut stmt = conn.prepare(
-            "SELECT id, content, priority, created_at, updated_at, is_archived 
-             FROM notes 
-             WHERE is_archived = 0 
-             ORDER BY created_at DESC 
-             LIMIT ?1"
-        )?;
-        
-        let notes = stmt.query_map(params![limit], |row| {
-            Ok(Note {
-                id: Some(row.get(0)?),
-                content: row.get(1)?,
-                priority: Priority::from_str(&row.get::<_, String>(2)?).unwrap(),
-                created_at: row.get::<_, String>(3)?.parse().unwrap(),
-                updated_at: row.get::<_, String>(4)?.parse().unwrap(),
-                is_archived: row.get::<_, i32>(5)? != 0,
-                tags: Vec::new(),
-            })
-        })?;
+        let sql = format!(
+            "SELECT {} 
+             FROM notes n
+             WHERE {} 
+             ORDER BY n.created_at DESC 
+             LIMIT {}",
+            NOTE_COLUMNS,
+            conditions,
+            filter.limit.map_or(-1, |l| l as i64)
+        );
         
-        let mut result = Vec::new();
-        for note in notes {
-            let mut note = note?;
-            note.tags = self.get_tags_for_note(note.id.unwrap())?;
-            result.push(note);
+        self.query_notes(&sql, params_from_iter(values))
+    }
+    
+    /// Read `text` as a search query in `mode`. Text that doesn't parse as the
+    /// query language, such as `"unterminated` or `user:id`, is taken as
+    /// literal words instead, and the reason returned alongside.
+    pub fn parse_query(text: &str, mode: SearchMode) -> anyhow::Result<(Query, Option<String>)> {
+        match mode {
+            SearchMode::Literal => Ok((Query::literal(text)?, None)),
+            SearchMode::RawFts => Ok((Query::Raw(text.to_string()), None)),
+            SearchMode::Query => match Query::parse(text) {
+                Ok(query) => Ok((query, None)),
+                Err(e) => Ok((Query::literal(text)?, Some(e.to_string()))),
+            },
         }
-        
-        Ok(result)
     }
     
-    pub fn list_today(&self, limit: usize) -> anyhow::Result<Vec<Note>> {
-        let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
-        let today_start = chrono::DateTime::<Utc>::from_naive_utc_and_offset(today_start, Utc);
-        
-        let conn = self.db.connection();
-        
-        let mut stmt = conn.prepare(
-            "SELECT id, content, priority, created_at, updated_at, is_archived 
-             FROM notes 
-             WHERE is_archived = 0 AND created_at >= ?1
-             ORDER BY created_at DESC 
-             LIMIT ?2"
-        )?;
-        
-        let notes = stmt.query_map(params![today_start.to_rfc3339(), limit], |row| {
-            Ok(Note {
-                id: Some(row.get(0)?),
-                content: row.get(1)?,
-                priority: Priority::from_str(&row.get::<_, String>(2)?).unwrap(),
-                created_at: row.get::<_, String>(3)?.parse().unwrap(),
-                updated_at: row.get::<_, String>(4)?.parse().unwrap(),
-                is_archived: row.get::<_, i32>(5)? != 0,
-                tags: Vec::new(),
-            })
-        })?;
-        
-        let mut result = Vec::new();
-        for note in notes {
-            let mut note = note?;
-            note.tags = self.get_tags_for_note(note.id.unwrap())?;
-            result.push(note);
+    /// Notes matching both `filter` and a parsed search query. With
+    /// `SearchOrder::Relevance`, notes are ranked by FTS5's bm25 score for the
+    /// query's words, boosted when a note is tagged with one of them and,
+    /// gently, when it is recent. A raw FTS5 expression that SQLite rejects is
+    /// searched for as literal words instead, with the reason in `fallback`.
+    pub fn search(&self, query: &Query, filter: &NoteFilter, order: SearchOrder) -> anyhow::Result<SearchResults> {
+        match self.check_query(query) {
+            Ok(()) => Ok(SearchResults { results: self.run_search(query, filter, order)?, fallback: None }),
+            // Everything but a raw expression is quoted before it reaches FTS5
+            Err(e) => match query {
+                Query::Raw(text) => Ok(SearchResults {
+                    results: self.run_search(&Query::literal(text)?, filter, order)?,
+                    fallback: Some(e.to_string()),
+                }),
+                _ => Err(e),
+            },
         }
-        
-        Ok(result)
     }
     
-    pub fn list_by_tag(&self, tag: &str, limit: usize) -> anyhow::Result<Vec<Note>> {
-        let conn = self.db.connection();
+    fn run_search(&self, query: &Query, filter: &NoteFilter, order: SearchOrder) -> anyhow::Result<Vec<SearchResult>> {
+        let (conditions, mut values) = Self::filter_conditions(filter);
+        let query_condition = query.to_sql(&mut values);
+        
+        let mut snippet = "NULL";
+        let mut matches = String::new();
+        let mut order_by = "n.created_at DESC".to_string();
+        
+        if let Some((table, expression, snippet_tokens)) = text_index(query) {
+            values.push(Value::Text(expression));
+            snippet = "m.snippet";
+            matches = format!(
+                "LEFT JOIN (SELECT rowid AS id, bm25({0}) AS rank, \
+                 snippet({0}, 0, char(2), char(3), '…', {1}) AS snippet \
+                 FROM {0} WHERE {0} MATCH ?{2}) m ON m.id = n.id",
+                table,
+                snippet_tokens,
+                values.len()
+            );
+            
+            if order == SearchOrder::Relevance {
+                let placeholders: Vec<String> = query.text_terms().iter()
+                    .map(|term| {
+                        values.push(Value::Text(term.to_lowercase()));
+                        format!("?{}", values.len())
+                    })
+                    .collect();
+                // bm25 is negative, so scaling it up ranks a note higher
+                order_by = format!(
+                    "m.rank IS NULL, \
+                     m.rank * (1.0 + 0.5 * (SELECT COUNT(*) FROM note_tags nt JOIN tags t ON nt.tag_id = t.id \
+                                            WHERE nt.note_id = n.id AND lower(t.name) IN ({}))) \
+                            * (1.0 + 0.5 / (1.0 + (julianday('now') - julianday(n.created_at)) / 30.0)), \
+                     n.created_at DESC",
+                    placeholders.join(", ")
+                );
+            }
+        }
         
-        let mut stmt = conn.prepare(
-            "SELECT n.id, n.content, n.priority, n.created_at, n.updated_at, n.is_archived 
-             FROM notes n
-             JOIN note_tags nt ON n.id = nt.note_id
-             JOIN tags t ON nt.tag_id = t.id
-             WHERE n.is_archived = 0 AND t.name = ?1
-             ORDER BY n.created_at DESC 
-             LIMIT ?2"
-        )?;
+        let sql = format!(
+            "SELECT {}, {}
+             FROM notes n {}
+             WHERE {} AND ({})
+             ORDER BY {}
+             LIMIT {}",
+            NOTE_COLUMNS,
+            snippet,
+            matches,
+            conditions,
+            query_condition,
+            order_by,
+            filter.limit.map_or(-1, |l| l as i64)
+        );
         
-        let notes = stmt.query_map(params![tag, limit], |row| {
-            Ok(Note {
-                id: Some(row.get(0)?),
-                content: row.get(1)?,
-                priority: Priority::from_str(&row.get::<_, String>(2)?).unwrap(),
-                created_at: row.get::<_, String>(3)?.parse().unwrap(),
-                updated_at: row.get::<_, String>(4)?.parse().unwrap(),
-                is_archived: row.get::<_, i32>(5)? != 0,
-                tags: Vec::new(),
-            })
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            Ok(SearchResult { note: note_from_row(row)?, snippet: row.get(8)? })
         })?;
         
-        let mut result = Vec::new();
-        for note in notes {
-            let mut note = note?;
-            note.tags = self.get_tags_for_note(note.id.unwrap())?;
-            result.push(note);
+        let mut results = Vec::new();
+        for result in rows {
+            let mut result = result?;
+            result.note.tags = self.get_tags_for_note(result.note.id.unwrap())?;
+            results.push(result);
         }
         
-        Ok(result)
+        Ok(results)
     }
     
-    pub fn search(&self, query: &str) -> anyhow::Result<Vec<Note>> {
-        let conn = self.db.connection();
-        
-        let mut stmt = conn.prepare(
-            "SELECT n.id, n.content, n.priority, n.created_at, n.updated_at, n.is_archived 
+    /// Archive each note, returning them as they now are.
+    pub fn archive_notes(&self, ids: &[i64]) -> anyhow::Result<Vec<Note>> {
+        self.operation("archive", || {
+            ids.iter().map(|id| self.set_archived(*id, true)).collect()
+        })
+    }
+    
+    pub fn unarchive_notes(&self, ids: &[i64]) -> anyhow::Result<Vec<Note>> {
+        self.operation("unarchive", || {
+            ids.iter().map(|id| self.set_archived(*id, false)).collect()
+        })
+    }
+    
+    /// Apply the given changes, returning the updated note, or `None` if they
+    /// leave the note as it was. An edit that changes nothing isn't timestamped,
+    /// journaled or recorded as a revision.
+    pub fn update_note(
+        &self,
+        id: i64,
+        content: Option<&str>,
+        priority: Option<Priority>,
+        add_tags: &[String],
+        remove_tags: &[String],
+    ) -> anyhow::Result<Option<Note>> {
+        self.operation("edit", || {
+            // Fails early with a friendly error if the note doesn't exist
+            let before = self.get_note(id)?;
+            
+            let mut tags = before.tags.clone();
+            for tag in add_tags {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+            tags.retain(|tag| !remove_tags.contains(tag));
+            
+            let unchanged = content.is_none_or(|content| content == before.content)
+                && priority.as_ref().is_none_or(|priority| *priority == before.priority)
+                && tags == before.tags;
+            if unchanged {
+                return Ok(None);
+            }
+            
+            self.track(id)?;
+            
+            // Notes created before history existed get their original state captured first
+            self.record_revision(id)?;
+            
+            let conn = self.db.connection();
+            
+            if let Some(content) = content {
+                conn.execute(
+                    "UPDATE notes SET content = ?1 WHERE id = ?2",
+                    params![content, id],
+                )?;
+            }
+            
+            if let Some(priority) = &priority {
+                conn.execute(
+                    "UPDATE notes SET priority = ?1 WHERE id = ?2",
+                    params![priority.to_string(), id],
+                )?;
+            }
+            
+            for tag in add_tags {
+                self.add_tag_to_note(id, tag)?;
+            }
+            
+            for tag in remove_tags {
+                self.remove_tag_from_note(id, tag)?;
+            }
+            
+            conn.execute(
+                "UPDATE notes SET updated_at = ?1 WHERE id = ?2",
+                params![Utc::now().to_rfc3339(), id],
+            )?;
+            
+            self.record_revision(id)?;
+            
+            self.get_note(id).map(Some)
+        })
+    }
+    
+    /// Move a note to the trash, returning it as it now is. Its tags stay
+    /// attached until the trash is emptied.
+    pub fn delete_note(&self, id: i64) -> anyhow::Result<Note> {
+        self.operation("delete", || {
+            // First check if note exists
+            let mut note = self.get_note(id)?;
+            self.track(id)?;
+            
+            let deleted_at = Utc::now();
+            let conn = self.db.connection();
+            conn.execute(
+                "UPDATE notes SET deleted_at = ?1 WHERE id = ?2",
+                params![deleted_at.to_rfc3339(), id],
+            )?;
+            
+            note.deleted_at = Some(deleted_at);
+            Ok(note)
+        })
+    }
+    
+    pub fn list_trash(&self) -> anyhow::Result<Vec<Note>> {
+        let sql = format!(
+            "SELECT {} 
              FROM notes n
-             JOIN notes_fts ON n.id = notes_fts.rowid
-             WHERE notes_fts MATCH ?1 AND n.is_archived = 0
-             ORDER BY n.created_at DESC"
-        )?;
-        
-        let notes = stmt.query_map(params![query], |row| {
-            Ok(Note {
-                id: Some(row.get(0)?),
-                content: row.get(1)?,
-                priority: Priority::from_str(&row.get::<_, String>(2)?).unwrap(),
-                created_at: row.get::<_, String>(3)?.parse().unwrap(),
-                updated_at: row.get::<_, String>(4)?.parse().unwrap(),
-                is_archived: row.get::<_, i32>(5)? != 0,
-                tags: Vec::new(),
-            })
-        })?;
-        
-        let mut result = Vec::new();
-        for note in notes {
-            let mut note = note?;
-            note.tags = self.get_tags_for_note(note.id.unwrap())?;
-            result.push(note);
-        }
+             WHERE n.deleted_at IS NOT NULL
+             ORDER BY n.deleted_at DESC",
+            NOTE_COLUMNS
+        );
         
-        Ok(result)
+        self.query_notes(&sql, [])
     }
     
-    pub fn delete_note(&self, id: i64) -> anyhow::Result<()> {
-        // First check if note exists
+    pub fn restore_note(&self, id: i64) -> anyhow::Result<Note> {
+        self.operation("restore", || {
+            let note = self.find_note(id)?
+                .ok_or_else(|| anyhow::anyhow!("Note with ID {} not found", id))?;
+            
+            if note.deleted_at.is_none() {
+                return Err(anyhow::anyhow!("Note {} is not in the trash", id));
+            }
+            self.track(id)?;
+            
+            let conn = self.db.connection();
+            conn.execute("UPDATE notes SET deleted_at = NULL WHERE id = ?1", params![id])?;
+            
+            self.get_note(id)
+        })
+    }
+    
+    /// Permanently delete trashed notes, optionally only those trashed before `older_than`.
+    /// Returns the number of notes purged. Purging is final and cannot be undone.
+    pub fn empty_trash(&self, older_than: Option<Duration>) -> anyhow::Result<usize> {
         let conn = self.db.connection();
-        let exists: bool = conn.query_row(
-            "SELECT EXISTS(SELECT 1 FROM notes WHERE id = ?1)",
-            params![id],
-            |row| row.get(0),
+        let tx = conn.unchecked_transaction()?;
+        
+        let cutoff = older_than.map(|age| (Utc::now() - age).to_rfc3339());
+        let mut stmt = conn.prepare(
+            "SELECT id FROM notes WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR deleted_at <= ?1)"
         )?;
+        let ids = stmt.query_map(params![cutoff], |row| row.get::<_, i64>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
         
-        if !exists {
-            return Err(anyhow::anyhow!("Note with ID {} not found", id));
+        for id in &ids {
+            conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
+            self.forget_note(*id)?;
         }
         
-        conn.execute("DELETE FROM notes WHERE id = ?1", params![id])?;
-        Ok(())
+        drop(stmt);
+        tx.commit()?;
+        Ok(ids.len())
     }
     
-    pub fn get_stats(&self) -> anyhow::Result<Stats> {
+    /// Counts for one notebook, or across all notebooks when `notebook_id` is `None`.
+    pub fn get_stats(&self, notebook_id: Option<i64>) -> anyhow::Result<Stats> {
         let conn = self.db.connection();
         
         let total: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0",
-            [],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND (?1 IS NULL OR notebook_id = ?1)",
+            params![notebook_id],
             |row| row.get(0),
         )?;
         
-        let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
-        let today_start = chrono::DateTime::<Utc>::from_naive_utc_and_offset(today_start, Utc);
+        let today_start = start_of_today();
         
         let today: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0 AND created_at >= ?1",
-            params![today_start.to_rfc3339()],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND created_at >= ?1 AND (?2 IS NULL OR notebook_id = ?2)",
+            params![today_start.to_rfc3339(), notebook_id],
             |row| row.get(0),
         )?;
         
         let week_start = today_start - Duration::days(7);
         let week: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0 AND created_at >= ?1",
-            params![week_start.to_rfc3339()],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND created_at >= ?1 AND (?2 IS NULL OR notebook_id = ?2)",
+            params![week_start.to_rfc3339(), notebook_id],
             |row| row.get(0),
         )?;
         
         let month_start = today_start - Duration::days(30);
         let month: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0 AND created_at >= ?1",
-            params![month_start.to_rfc3339()],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND created_at >= ?1 AND (?2 IS NULL OR notebook_id = ?2)",
+            params![month_start.to_rfc3339(), notebook_id],
             |row| row.get(0),
         )?;
         
         let urgent: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0 AND priority = 'urgent'",
-            [],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND priority = 'urgent' AND (?1 IS NULL OR notebook_id = ?1)",
+            params![notebook_id],
             |row| row.get(0),
         )?;
         
         let high: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0 AND priority = 'high'",
-            [],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND priority = 'high' AND (?1 IS NULL OR notebook_id = ?1)",
+            params![notebook_id],
             |row| row.get(0),
         )?;
         
         let medium: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0 AND priority = 'medium'",
-            [],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND priority = 'medium' AND (?1 IS NULL OR notebook_id = ?1)",
+            params![notebook_id],
             |row| row.get(0),
         )?;
         
         let low: usize = conn.query_row(
-            "SELECT COUNT(*) FROM notes WHERE is_archived = 0 AND priority = 'low'",
-            [],
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 0 AND priority = 'low' AND (?1 IS NULL OR notebook_id = ?1)",
+            params![notebook_id],
+            |row| row.get(0),
+        )?;
+        
+        let archived: usize = conn.query_row(
+            "SELECT COUNT(*) FROM notes WHERE deleted_at IS NULL AND is_archived = 1 AND (?1 IS NULL OR notebook_id = ?1)",
+            params![notebook_id],
             |row| row.get(0),
         )?;
         
@@ -277,9 +430,97 @@ impl NoteService {
             high,
             medium,
             low,
+            archived,
         })
     }
     
+    fn set_archived(&self, id: i64, archived: bool) -> anyhow::Result<Note> {
+        let mut note = self.get_note(id)?;
+        self.track(id)?;
+        
+        let conn = self.db.connection();
+        conn.execute(
+            "UPDATE notes SET is_archived = ?1 WHERE id = ?2",
+            params![archived as i32, id],
+        )?;
+        
+        note.is_archived = archived;
+        Ok(note)
+    }
+    
+    /// Run the query's FTS5 expression on its own, so a syntax error in it is
+    /// reported as such rather than as a failure of the whole search.
+    fn check_query(&self, query: &Query) -> anyhow::Result<()> {
+        let Some((table, expression, _)) = text_index(query) else {
+            return Ok(());
+        };
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(&format!("SELECT 1 FROM {0} WHERE {0} MATCH ?1 LIMIT 1", table))?;
+        match stmt.exists([expression]) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(anyhow::anyhow!("Invalid full-text query: {}", fts_error(&e.to_string()))),
+        }
+    }
+    
+    /// SQL conditions (on `notes n`) and their positional parameters for a filter.
+    fn filter_conditions(filter: &NoteFilter) -> (String, Vec<Value>) {
+        let mut conditions = vec!["n.deleted_at IS NULL".to_string(), filter.archived.sql_condition("n")];
+        let mut values = Vec::new();
+        
+        if let Some(notebook_id) = filter.notebook_id {
+            values.push(Value::Integer(notebook_id));
+            conditions.push(format!("n.notebook_id = ?{}", values.len()));
+        }
+        
+        if let Some(tag) = &filter.tag {
+            values.push(Value::Text(tag.clone()));
+            conditions.push(format!(
+                "n.id IN (SELECT nt.note_id FROM note_tags nt JOIN tags t ON nt.tag_id = t.id WHERE t.name = ?{})",
+                values.len()
+            ));
+        }
+        
+        if let Some(priority) = &filter.priority {
+            values.push(Value::Text(priority.to_string().to_string()));
+            conditions.push(format!("n.priority = ?{}", values.len()));
+        }
+        
+        if let Some(since) = filter.since {
+            values.push(Value::Text(since.to_rfc3339()));
+            conditions.push(format!("n.created_at >= ?{}", values.len()));
+        }
+        
+        if let Some(until) = filter.until {
+            values.push(Value::Text(until.to_rfc3339()));
+            conditions.push(format!("n.created_at < ?{}", values.len()));
+        }
+        
+        (conditions.join(" AND "), values)
+    }
+    
+    /// Look up a note by ID, including notes in the trash.
+    fn find_note(&self, id: i64) -> anyhow::Result<Option<Note>> {
+        let sql = format!("SELECT {} FROM notes n WHERE n.id = ?1", NOTE_COLUMNS);
+        Ok(self.query_notes(&sql, params![id])?.into_iter().next())
+    }
+    
+    /// Run a query selecting `NOTE_COLUMNS` and load the tags for each resulting note.
+    fn query_notes<P: rusqlite::Params>(&self, sql: &str, params: P) -> anyhow::Result<Vec<Note>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(sql)?;
+        
+        let notes = stmt.query_map(params, note_from_row)?;
+        
+        let mut result = Vec::new();
+        for note in notes {
+            let mut note = note?;
+            note.tags = self.get_tags_for_note(note.id.unwrap())?;
+            result.push(note);
+        }
+        
+        Ok(result)
+    }
+    
     fn add_tag_to_note(&self, note_id: i64, tag: &str) -> anyhow::Result<()> {
         let conn = self.db.connection();
         
@@ -304,6 +545,18 @@ impl NoteService {
         Ok(())
     }
     
+    fn remove_tag_from_note(&self, note_id: i64, tag: &str) -> anyhow::Result<()> {
+        let conn = self.db.connection();
+        
+        conn.execute(
+            "DELETE FROM note_tags
+             WHERE note_id = ?1 AND tag_id = (SELECT id FROM tags WHERE name = ?2)",
+            params![note_id, tag],
+        )?;
+        
+        Ok(())
+    }
+    
     fn get_tags_for_note(&self, note_id: i64) -> anyhow::Result<Vec<String>> {
         let conn = self.db.connection();
         
@@ -319,3 +572,125 @@ impl NoteService {
         Ok(tags)
     }
 }
+
+/// The index to MATCH all the words a query looks for against at once, for
+/// snippets and ranking, with the expression and how many tokens a snippet
+/// spans. Substrings are looked up in the trigram index, whose tokens are
+/// three characters long, so its snippets need more of them.
+fn text_index(query: &Query) -> Option<(&'static str, String, i32)> {
+    match (query.fts_expression(), query.substring_expression()) {
+        (Some(expression), _) => Some(("notes_fts", expression, 16)),
+        (None, Some(expression)) => Some(("notes_trigram", expression, 64)),
+        (None, None) => None,
+    }
+}
+
+/// Explain an FTS5 error message in terms of the query the user typed.
+fn fts_error(message: &str) -> String {
+    if let Some(near) = message.split_once("syntax error near \"").map(|(_, rest)| rest.trim_end_matches('"')) {
+        return if near.is_empty() {
+            "it ends unexpectedly; check for an unclosed quote or a trailing AND, OR or NOT".to_string()
+        } else {
+            format!("syntax error near '{}'", near)
+        };
+    }
+    if message.contains("unterminated string") {
+        return "a quote is never closed".to_string();
+    }
+    if let Some(column) = message.strip_prefix("no such column: ") {
+        return format!("'{}:' reads as a column filter, but the index only has a 'content' column", column);
+    }
+    message.trim_start_matches("fts5: ").to_string()
+}
+
+/// A note from a row whose first columns are `NOTE_COLUMNS`, without its tags.
+fn note_from_row(row: &rusqlite::Row) -> rusqlite::Result<Note> {
+    Ok(Note {
+        id: Some(row.get(0)?),
+        content: row.get(1)?,
+        priority: Priority::from_str(&row.get::<_, String>(2)?).unwrap(),
+        created_at: row.get::<_, String>(3)?.parse().unwrap(),
+        updated_at: row.get::<_, String>(4)?.parse().unwrap(),
+        is_archived: row.get::<_, i32>(5)? != 0,
+        tags: Vec::new(),
+        deleted_at: row.get::<_, Option<String>>(6)?.map(|s| s.parse().unwrap()),
+        notebook: row.get(7)?,
+    })
+}
+
+/// A service over a fresh, fully migrated database in a temporary directory,
+/// which is removed when the returned guard is dropped.
+#[cfg(test)]
+fn test_service() -> (tempfile::TempDir, NoteService) {
+    let dir = tempfile::tempdir().unwrap();
+    let db = Database::open(&dir.path().join("notes.db")).unwrap();
+    db.migrate().unwrap();
+    (dir, NoteService::new(db))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn search(service: &NoteService, text: &str, mode: SearchMode) -> (Vec<String>, Option<String>) {
+        let (query, parse_fallback) = NoteService::parse_query(text, mode).unwrap();
+        let found = service.search(&query, &NoteFilter::default(), SearchOrder::Newest).unwrap();
+        let contents = found.results.into_iter().map(|result| result.note.content).collect();
+        (contents, parse_fallback.or(found.fallback))
+    }
+    
+    #[test]
+    fn invalid_raw_fts_falls_back_to_literal_words() {
+        let (_dir, service) = test_service();
+        service.add_note("templates in C++", Vec::new(), Priority::Medium, None).unwrap();
+        
+        let (found, fallback) = search(&service, "templates +", SearchMode::RawFts);
+        assert_eq!(found, vec!["templates in C++"]);
+        assert!(fallback.unwrap().starts_with("Invalid full-text query: it ends unexpectedly"));
+        
+        let (found, fallback) = search(&service, "NEAR(templates C)", SearchMode::RawFts);
+        assert_eq!(found, vec!["templates in C++"]);
+        assert_eq!(fallback, None);
+    }
+    
+    #[test]
+    fn unparsable_queries_fall_back_to_literal_words() {
+        let (_dir, service) = test_service();
+        service.add_note("map user:id to the \"unterminated row", Vec::new(), Priority::Medium, None).unwrap();
+        
+        let (found, fallback) = search(&service, "\"unterminated", SearchMode::Query);
+        assert_eq!(found.len(), 1);
+        assert_eq!(fallback.as_deref(), Some("Unterminated quote at '\"unterminated' in search query"));
+        
+        let (found, fallback) = search(&service, "user:id", SearchMode::Query);
+        assert_eq!(found.len(), 1);
+        assert!(fallback.unwrap().starts_with("Unknown search field 'user'"));
+        
+        let (found, fallback) = search(&service, "tag:map", SearchMode::Literal);
+        assert_eq!(found.len(), 0);
+        assert_eq!(fallback, None);
+    }
+    
+    #[test]
+    fn edits_that_change_nothing_leave_the_note_alone() {
+        let (_dir, service) = test_service();
+        let note = service.add_note("x", vec!["a".to_string()], Priority::High, None).unwrap();
+        let id = note.id.unwrap();
+        let revisions = service.list_revisions(id).unwrap().len();
+        
+        let tag = |name: &str| vec![name.to_string()];
+        assert!(service.update_note(id, Some("x"), Some(Priority::High), &[], &[]).unwrap().is_none());
+        assert!(service.update_note(id, None, None, &tag("a"), &tag("b")).unwrap().is_none());
+        assert!(service.update_note(id, None, None, &tag("c"), &tag("c")).unwrap().is_none());
+        
+        assert_eq!(service.get_note(id).unwrap().updated_at, note.updated_at);
+        assert_eq!(service.list_revisions(id).unwrap().len(), revisions);
+        // The only journaled operation is still the add
+        service.undo(1).unwrap();
+        assert!(service.find_note(id).unwrap().is_none());
+        
+        service.redo(1).unwrap();
+        let edited = service.update_note(id, None, None, &tag("b"), &[]).unwrap().unwrap();
+        assert_eq!(edited.tags.len(), 2);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::models::Notebook;
+use chrono::Utc;
+use rusqlite::{params, OptionalExtension};
+
+use super::NoteService;
+
+impl NoteService {
+    pub fn create_notebook(&self, name: &str) -> anyhow::Result<Notebook> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow::anyhow!("Notebook name cannot be empty"));
+        }
+        if self.find_notebook(name)?.is_some() {
+            return Err(anyhow::anyhow!("Notebook '{}' already exists", name));
+        }
+        
+        let conn = self.db.connection();
+        conn.execute(
+            "INSERT INTO notebooks (name, created_at) VALUES (?1, ?2)",
+            params![name, Utc::now().to_rfc3339()],
+        )?;
+        
+        let notebook = self.get_notebook(name)?;
+        self.track_created_notebook(&notebook);
+        Ok(notebook)
+    }
+    
+    pub fn list_notebooks(&self) -> anyhow::Result<Vec<Notebook>> {
+        let conn = self.db.connection();
+        let mut stmt = conn.prepare(
+            "SELECT b.id, b.name, b.created_at,
+                    (SELECT COUNT(*) FROM notes n WHERE n.notebook_id = b.id AND n.deleted_at IS NULL)
+             FROM notebooks b
+             ORDER BY b.name"
+        )?;
+        
+        let notebooks = stmt.query_map([], |row| {
+            Ok(Notebook {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get::<_, String>(2)?.parse().unwrap(),
+                note_count: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(notebooks)
+    }
+    
+    pub fn get_notebook(&self, name: &str) -> anyhow::Result<Notebook> {
+        self.find_notebook(name)?.ok_or_else(|| {
+            anyhow::anyhow!("Notebook '{}' not found. Create it with: ezn notebook create {}", name, name)
+        })
+    }
+    
+    pub fn rename_notebook(&self, name: &str, new_name: &str) -> anyhow::Result<Notebook> {
+        let notebook = self.get_notebook(name)?;
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(anyhow::anyhow!("Notebook name cannot be empty"));
+        }
+        if self.find_notebook(new_name)?.is_some() {
+            return Err(anyhow::anyhow!("Notebook '{}' already exists", new_name));
+        }
+        
+        let conn = self.db.connection();
+        conn.execute(
+            "UPDATE notebooks SET name = ?1 WHERE id = ?2",
+            params![new_name, notebook.id],
+        )?;
+        
+        self.get_notebook(new_name)
+    }
+    
+    /// Delete a notebook. Notebooks that still hold notes (including trashed ones)
+    /// are only deleted when `move_to` names a notebook to receive them.
+    pub fn delete_notebook(&self, name: &str, move_to: Option<&str>) -> anyhow::Result<()> {
+        let notebook = self.get_notebook(name)?;
+        if notebook.id == self.current_notebook()?.id {
+            return Err(anyhow::anyhow!(
+                "Cannot delete the current notebook. Switch first with: ezn notebook use <name>"
+            ));
+        }
+        
+        let target = move_to.map(|n| self.get_notebook(n)).transpose()?;
+        if target.as_ref().is_some_and(|t| t.id == notebook.id) {
+            return Err(anyhow::anyhow!("Cannot move notes into the notebook being deleted"));
+        }
+        
+        self.operation("move", || {
+            let conn = self.db.connection();
+            let mut stmt = conn.prepare("SELECT id FROM notes WHERE notebook_id = ?1")?;
+            let note_ids = stmt.query_map(params![notebook.id], |row| row.get::<_, i64>(0))?
+                .collect::<Result<Vec<_>, _>>()?;
+            
+            if !note_ids.is_empty() {
+                let target = target.as_ref().ok_or_else(|| anyhow::anyhow!(
+                    "Notebook '{}' still has {} notes. Use --move-to <notebook> to keep them",
+                    name, note_ids.len()
+                ))?;
+                
+                for id in &note_ids {
+                    self.track(*id)?;
+                }
+                conn.execute(
+                    "UPDATE notes SET notebook_id = ?1 WHERE notebook_id = ?2",
+                    params![target.id, notebook.id],
+                )?;
+            }
+            
+            conn.execute("DELETE FROM notebooks WHERE id = ?1", params![notebook.id])?;
+            Ok(())
+        })
+    }
+    
+    /// The notebook new notes go into and listings are scoped to by default.
+    pub fn current_notebook(&self) -> anyhow::Result<Notebook> {
+        let conn = self.db.connection();
+        let id: Option<String> = conn.query_row(
+            "SELECT value FROM settings WHERE key = 'current_notebook'",
+            [],
+            |row| row.get(0),
+        ).optional()?;
+        
+        let id: i64 = id.and_then(|v| v.parse().ok()).unwrap_or(1);
+        self.list_notebooks()?
+            .into_iter()
+            .find(|b| b.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Current notebook no longer exists. Pick one with: ezn notebook use <name>"))
+    }
+    
+    pub fn set_current_notebook(&self, name: &str) -> anyhow::Result<Notebook> {
+        let notebook = self.get_notebook(name)?;
+        
+        let conn = self.db.connection();
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES ('current_notebook', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![notebook.id.to_string()],
+        )?;
+        
+        Ok(notebook)
+    }
+    
+    /// Look up a notebook by name, creating it if it doesn't exist.
+    pub(super) fn ensure_notebook(&self, name: &str) -> anyhow::Result<i64> {
+        match self.find_notebook(name)? {
+            Some(notebook) => Ok(notebook.id),
+            None => Ok(self.create_notebook(name)?.id),
+        }
+    }
+    
+    pub(super) fn notebook_exists(&self, id: i64) -> anyhow::Result<bool> {
+        Ok(self.db.connection().query_row(
+            "SELECT EXISTS(SELECT 1 FROM notebooks WHERE id = ?1)",
+            params![id],
+            |row| row.get(0),
+        )?)
+    }
+    
+    fn find_notebook(&self, name: &str) -> anyhow::Result<Option<Notebook>> {
+        Ok(self.list_notebooks()?.into_iter().find(|b| b.name == name))
+    }
+}
@@ -0,0 +1,158 @@
+use serde::Serialize;
+
+/// A single line in a unified diff.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", content = "text", rename_all = "lowercase")]
+pub enum DiffLine<'a> {
+    Context(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// A group of nearby changes with surrounding context, as in `diff -u`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hunk<'a> {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<DiffLine<'a>>,
+}
+
+impl Hunk<'_> {
+    pub fn header(&self) -> String {
+        format!("@@ -{},{} +{},{} @@", self.old_start, self.old_len, self.new_start, self.new_len)
+    }
+}
+
+/// Line-based unified diff of `old` and `new` with `context` lines around each change.
+pub fn unified_diff<'a>(old: &'a str, new: &'a str, context: usize) -> Vec<Hunk<'a>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+    
+    // Position in each file *before* every op, so hunks can report line numbers
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut o, mut n) = (0, 0);
+    for op in &ops {
+        positions.push((o, n));
+        match op {
+            DiffLine::Context(_) => { o += 1; n += 1; }
+            DiffLine::Removed(_) => o += 1,
+            DiffLine::Added(_) => n += 1,
+        }
+    }
+    
+    let changes: Vec<usize> = ops.iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffLine::Context(_)))
+        .map(|(i, _)| i)
+        .collect();
+    
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < changes.len() {
+        let start = changes[i].saturating_sub(context);
+        let mut end = (changes[i] + context + 1).min(ops.len());
+        
+        // Merge changes whose context windows touch
+        while i + 1 < changes.len() && changes[i + 1].saturating_sub(context) <= end {
+            i += 1;
+            end = (changes[i] + context + 1).min(ops.len());
+        }
+        
+        let lines = ops[start..end].to_vec();
+        let old_len = lines.iter().filter(|l| !matches!(l, DiffLine::Added(_))).count();
+        let new_len = lines.iter().filter(|l| !matches!(l, DiffLine::Removed(_))).count();
+        let (old_pos, new_pos) = positions[start];
+        
+        hunks.push(Hunk {
+            old_start: if old_len == 0 { old_pos } else { old_pos + 1 },
+            old_len,
+            new_start: if new_len == 0 { new_pos } else { new_pos + 1 },
+            new_len,
+            lines,
+        });
+        i += 1;
+    }
+    
+    hunks
+}
+
+/// Longest-common-subsequence diff; notes are small enough for the O(n·m) table.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffLine<'a>> {
+    let (n, m) = (old.len(), new.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            ops.push(DiffLine::Context(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            ops.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|l| DiffLine::Removed(l)));
+    ops.extend(new[j..].iter().map(|l| DiffLine::Added(l)));
+    
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const OLD: &str = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj";
+    
+    fn headers(old: &str, new: &str, context: usize) -> Vec<String> {
+        unified_diff(old, new, context).iter().map(Hunk::header).collect()
+    }
+    
+    #[test]
+    fn reports_changed_lines_with_context() {
+        let hunks = unified_diff(OLD, "a\nB\nc\nd\ne\nf\ng\nh\ni\nj", 1);
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].header(), "@@ -1,3 +1,3 @@");
+        assert_eq!(hunks[0].lines, vec![
+            DiffLine::Context("a"),
+            DiffLine::Removed("b"),
+            DiffLine::Added("B"),
+            DiffLine::Context("c"),
+        ]);
+        
+        assert!(unified_diff(OLD, OLD, 3).is_empty());
+    }
+    
+    #[test]
+    fn merges_changes_whose_context_touches() {
+        // b and e are two lines apart, so their one line of context meets
+        assert_eq!(headers(OLD, "a\nB\nc\nd\nE\nf\ng\nh\ni\nj", 1), vec!["@@ -1,6 +1,6 @@"]);
+        // b and f are three apart, which leaves a line between the hunks
+        assert_eq!(headers(OLD, "a\nB\nc\nd\ne\nF\ng\nh\ni\nj", 1), vec!["@@ -1,3 +1,3 @@", "@@ -5,3 +5,3 @@"]);
+        assert_eq!(headers(OLD, "a\nB\nc\nd\ne\nF\ng\nh\ni\nj", 3), vec!["@@ -1,9 +1,9 @@"]);
+    }
+    
+    #[test]
+    fn numbers_insertions_and_deletions_like_diff_u() {
+        assert_eq!(headers("", "x\ny", 3), vec!["@@ -0,0 +1,2 @@"]);
+        assert_eq!(headers("x\ny", "", 3), vec!["@@ -1,2 +0,0 @@"]);
+        assert_eq!(headers(OLD, "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk", 2), vec!["@@ -9,2 +9,3 @@"]);
+        assert_eq!(headers(OLD, "b\nc\nd\ne\nf\ng\nh\ni\nj", 0), vec!["@@ -1,1 +0,0 @@"]);
+    }
+}
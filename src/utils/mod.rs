@@ -0,0 +1,115 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+pub mod diff;
+
+/// Parse a human-friendly age such as `30d`, `12h`, `2w` or `1y`.
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    
+    let amount: i64 = number.parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration: {}. Use e.g. 12h, 30d, 2w, 1y", s))?;
+    
+    match unit {
+        "h" => Ok(Duration::hours(amount)),
+        "d" | "" => Ok(Duration::days(amount)),
+        "w" => Ok(Duration::weeks(amount)),
+        "y" => Ok(Duration::days(amount * 365)),
+        _ => Err(anyhow::anyhow!("Invalid duration unit in {}. Use h, d, w or y", s)),
+    }
+}
+
+/// Midnight (UTC) at the start of the current day.
+pub fn start_of_today() -> DateTime<Utc> {
+    let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
+    DateTime::<Utc>::from_naive_utc_and_offset(today_start, Utc)
+}
+
+/// Parse a `--since`/`--until` value: a date (`2024-03-01`, midnight UTC), an
+/// RFC 3339 timestamp, or an age relative to now (`7d`). With `end_of_day`, a
+/// bare date means the end of that day rather than its start.
+pub fn parse_date(s: &str, end_of_day: bool) -> anyhow::Result<DateTime<Utc>> {
+    let s = s.trim();
+    
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let date = if end_of_day { date + Duration::days(1) } else { date };
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+        return Ok(DateTime::<Utc>::from_naive_utc_and_offset(midnight, Utc));
+    }
+    
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    
+    parse_duration(s)
+        .map(|age| Utc::now() - age)
+        .map_err(|_| anyhow::anyhow!("Invalid date: {}. Use e.g. 2024-03-01 or an age like 7d", s))
+}
+
+/// Lowercase ASCII words joined by dashes, for file names. At most 50 characters.
+pub fn slugify(text: &str) -> String {
+    let words = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_lowercase());
+    
+    let mut slug = String::new();
+    for word in words {
+        if slug.len() + word.len() + 1 > 50 {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word);
+    }
+    slug
+}
+
+/// How many single-character insertions, deletions, substitutions or swaps
+/// of neighbouring characters turn `a` into `b`. Counting swaps as one edit
+/// keeps the most common typo, `teh` for `the`, close.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // rows[i][j] is the distance between the first i characters of a and the first j of b
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("meeting", "meeting"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("meting", "meeting"), 1);
+        assert_eq!(edit_distance("mating", "meeting"), 2);
+        // A swap of neighbours is one edit, not two
+        assert_eq!(edit_distance("teh", "the"), 1);
+        assert_eq!(edit_distance("recieve", "receive"), 1);
+        assert_eq!(edit_distance("café", "cafe"), 1);
+    }
+}
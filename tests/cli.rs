@@ -0,0 +1,88 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+/// Run `ezn` against its own database and config, returning stdout parsed as JSON.
+fn ezn_json(dir: &Path, args: &[&str]) -> Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_ezn"))
+        .current_dir(dir)
+        .env("EZNOTE_CONFIG", dir.join("config.toml"))
+        .env_remove("EZNOTE_DB")
+        .arg("--db").arg(dir.join("notes.db"))
+        .args(["--output", "json"])
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|e| panic!("{}: {}", e, String::from_utf8_lossy(&output.stdout)))
+}
+
+#[test]
+fn mutating_commands_print_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let note = ezn_json(dir.path(), &["add", "Fix the login bug", "--tag", "bug"]);
+    let id = note["id"].to_string();
+    
+    let unchanged = ezn_json(dir.path(), &["edit", &id, "--add-tag", "bug"]);
+    assert_eq!(unchanged, serde_json::json!({ "changed": false }));
+    
+    let archived = ezn_json(dir.path(), &["archive", &id]);
+    assert_eq!(archived[0]["is_archived"], true);
+    
+    let notebook = ezn_json(dir.path(), &["notebook", "create", "work"]);
+    assert_eq!(notebook["name"], "work");
+    
+    // Without --force the prompt reads an empty stdin and cancels
+    let cancelled = ezn_json(dir.path(), &["delete", &id]);
+    assert_eq!(cancelled, serde_json::json!({ "changed": false }));
+    
+    let deleted = ezn_json(dir.path(), &["delete", &id, "--force"]);
+    assert_eq!(deleted["id"], note["id"]);
+    assert!(deleted["deleted_at"].is_string());
+    
+    let emptied = ezn_json(dir.path(), &["trash", "empty", "--force"]);
+    assert_eq!(emptied["purged"], 1);
+}
+
+#[test]
+fn search_rows_keep_the_snippet() {
+    let dir = tempfile::tempdir().unwrap();
+    ezn_json(dir.path(), &["add", "Deploy the staging server"]);
+    
+    let rows = ezn_json(dir.path(), &["search", "staging"]);
+    assert_eq!(rows[0]["content"], "Deploy the staging server");
+    assert!(rows[0]["snippet"].as_str().unwrap().contains("\u{2}staging\u{3}"));
+    assert_eq!(rows[0]["fallback"], Value::Null);
+}
+
+#[test]
+fn list_all_covers_every_notebook_and_archived_notes() {
+    let dir = tempfile::tempdir().unwrap();
+    ezn_json(dir.path(), &["notebook", "create", "work"]);
+    ezn_json(dir.path(), &["add", "In the default notebook"]);
+    let archived = ezn_json(dir.path(), &["add", "Archived work note", "-n", "work"]);
+    ezn_json(dir.path(), &["archive", &archived["id"].to_string()]);
+    
+    assert_eq!(ezn_json(dir.path(), &["list"]).as_array().unwrap().len(), 1);
+    assert_eq!(ezn_json(dir.path(), &["list", "-n", "work", "--include-archived"]).as_array().unwrap().len(), 1);
+    assert_eq!(ezn_json(dir.path(), &["list", "--all"]).as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn export_takes_the_format_and_path_as_arguments() {
+    let dir = tempfile::tempdir().unwrap();
+    ezn_json(dir.path(), &["add", "Publish the team notes", "--tag", "docs"]);
+    
+    let site = dir.path().join("site");
+    let exported = ezn_json(dir.path(), &["export", "html", site.to_str().unwrap()]);
+    assert_eq!(exported["format"], "html");
+    assert_eq!(exported["count"], 1);
+    assert!(site.join("index.html").is_file());
+    
+    let org = dir.path().join("notes.org");
+    ezn_json(dir.path(), &["export", "org", org.to_str().unwrap()]);
+    assert!(std::fs::read_to_string(&org).unwrap().contains("* TODO [#C] Publish the team notes"));
+}